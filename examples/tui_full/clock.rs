@@ -0,0 +1,170 @@
+//! # Wall clock
+//!
+//! Local date and time: `chrono::Local` on Linux;
+//! on Cortex-M, the host time read with semihosting `SYS_TIME` at startup,
+//! advanced with the SysTick uptime counter.
+//! Tests use the fixed time, so the rendered screens do not change.
+//!
+//! On Cortex-M, the uptime is also the monotonic clock of the PALs
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+
+#[cfg(target_os = "none")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Broken-down date and time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    pub ms: u16,
+}
+
+impl DateTime {
+    /// Converts the seconds since the Unix epoch
+    pub fn from_unix(secs: u64, ms: u16) -> Self {
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = secs / 86_400;
+        let secs_of_day = secs % 86_400;
+
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as u64;
+
+        DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs_of_day / 3600) as u8,
+            min: (secs_of_day / 60 % 60) as u8,
+            sec: (secs_of_day % 60) as u8,
+            ms,
+        }
+    }
+
+    /// `YY-MM-DD HH:MM:SS`
+    pub fn date_time_str(&self) -> String {
+        format!(
+            "{:02}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year % 100,
+            self.month,
+            self.day,
+            self.hour,
+            self.min,
+            self.sec
+        )
+    }
+
+    /// `HH:MM:SS.mmm `, for the logs
+    pub fn time_str(&self) -> String {
+        format!("{:02}:{:02}:{:02}.{:03} ", self.hour, self.min, self.sec, self.ms)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+#[cfg(all(target_os = "linux", not(test)))]
+pub fn now() -> DateTime {
+    use chrono::{Datelike, Timelike};
+
+    let local_time = chrono::Local::now();
+    DateTime {
+        year: local_time.year() as u16,
+        month: local_time.month() as u8,
+        day: local_time.day() as u8,
+        hour: local_time.hour() as u8,
+        min: local_time.minute() as u8,
+        sec: local_time.second() as u8,
+        ms: (local_time.timestamp_subsec_millis() % 1000) as u16,
+    }
+}
+
+/// 2023-11-14 22:13:20
+#[cfg(test)]
+pub fn now() -> DateTime {
+    DateTime::from_unix(1_700_000_000, 0)
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Core clock of the LM3S6965, feeding the SysTick and the UART baud rate generator
+#[cfg(target_os = "none")]
+pub const CORE_CLOCK_HZ: u32 = 32_000_000;
+
+/// Milliseconds since `init()`, counted by the SysTick interrupt
+#[cfg(target_os = "none")]
+static UPTIME_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Host time at `init()`, in seconds since the Unix epoch
+#[cfg(target_os = "none")]
+static START_SECS: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(target_os = "none")]
+#[cortex_m_rt::exception]
+fn SysTick() {
+    UPTIME_MS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Starts the 1ms SysTick interrupt and reads the host time
+#[cfg(target_os = "none")]
+pub fn init(mut syst: cortex_m::peripheral::SYST) {
+    use cortex_m::peripheral::syst::SystClkSource;
+
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(CORE_CLOCK_HZ / 1000 - 1);
+    syst.clear_current();
+    syst.enable_interrupt();
+    syst.enable_counter();
+
+    // the UART builds run without semihosting - under QEMU it is not enabled,
+    // on the hardware without the debugger attached the call would stop the CPU
+    if !cfg!(feature = "uart") {
+        // https://developer.arm.com/documentation/dui0471/e/semihosting/sys-time--0x11-
+        let secs = unsafe { cortex_m_semihosting::syscall!(TIME) } as u32;
+        START_SECS.store(secs, Ordering::Relaxed);
+    }
+}
+
+/// Milliseconds since `init()`; wraps after ~49 days
+#[cfg(target_os = "none")]
+pub fn uptime_ms() -> u32 {
+    UPTIME_MS.load(Ordering::Relaxed)
+}
+
+/// Milliseconds elapsed since the `uptime_ms()` timestamp; safe for the wrap-around
+#[cfg(target_os = "none")]
+pub fn elapsed_ms(since: u32) -> u32 {
+    uptime_ms().wrapping_sub(since)
+}
+
+/// Sleeps for `ms`; the CPU is woken up by the SysTick or other interrupts
+#[cfg(target_os = "none")]
+pub fn sleep_ms(ms: u32) {
+    let started_at = uptime_ms();
+
+    while elapsed_ms(started_at) < ms {
+        cortex_m::asm::wfi();
+    }
+}
+
+/// Host time from the `init()`, advanced with the uptime; UTC, as the host reports it
+#[cfg(target_os = "none")]
+pub fn now() -> DateTime {
+    let uptime = uptime_ms();
+    let secs = START_SECS.load(Ordering::Relaxed) as u64 + (uptime / 1000) as u64;
+    DateTime::from_unix(secs, (uptime % 1000) as u16)
+}
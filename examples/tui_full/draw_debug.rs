@@ -0,0 +1,128 @@
+//! # Draw debugging
+//!
+//! When enabled - with the `draw_debug` feature at startup, or with F7 at runtime -
+//! the invalidated widgets and the windows drawn as a whole are flashed before being repainted,
+//! the widget ids are logged, and every flush is delayed, so the drawing process can be followed on the screen
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use rtwins::wgt::{self, WId, WindowState};
+use rtwins::TERM;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Delay after each flush [ms]
+const FLUSH_DELAY_MS: u16 = 50;
+/// How long the invalidated areas are highlighted [ms]
+const FLASH_MS: u16 = 150;
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(feature = "draw_debug"));
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(en: bool) {
+    ENABLED.store(en, Ordering::Relaxed);
+}
+
+/// Logs and highlights the invalidated widgets of the window;
+/// the window keeps them invalidated, so they are repainted next
+pub fn flash_invalidated(ws: &mut dyn WindowState) {
+    let mut wids: Vec<WId> = Vec::new();
+    ws.get_invalidated(&mut wids);
+
+    if wids.is_empty() {
+        return;
+    }
+
+    rtwins::tr_debug!("Redraw: {:?}", wids);
+    flash(ws, &wids);
+    ws.invalidate_many(&wids);
+}
+
+/// Highlights the whole window, that is going to be repainted
+pub fn flash_window(ws: &dyn WindowState) {
+    rtwins::tr_debug!("Redraw: window");
+    flash(ws, &[wgt::WIDGET_ID_ALL]);
+}
+
+fn flash(ws: &dyn WindowState, wids: &[WId]) {
+    if let Some(mut term_guard) = TERM.try_lock() {
+        term_guard.write_str(rtwins::csi!("43m"));
+
+        for wid in wids.iter() {
+            let wgt_opt = if *wid == wgt::WIDGET_ID_ALL {
+                ws.get_widgets().first()
+            }
+            else {
+                wgt::find_by_id(ws.get_widgets(), *wid)
+            };
+
+            // hidden widgets would not be repainted
+            if let Some(wgt) = wgt_opt.filter(|w| ws.is_visible(w)) {
+                let coord = wgt::get_screen_coord(ws, wgt);
+
+                for row in 0..wgt.size.height as u16 {
+                    term_guard.move_to(coord.col as u16, coord.row as u16 + row);
+                    term_guard.write_char_n(' ', wgt.size.width as i16);
+                }
+            }
+        }
+
+        term_guard.write_str(rtwins::csi!("0m"));
+        term_guard.flush_buff();
+        term_guard.pal.as_mut().sleep(FLASH_MS);
+    }
+    else {
+        rtwins::tr_warn!("Cannot lock the term");
+    }
+}
+
+/// PAL wrapper slowing down the flushes while the draw debugging is enabled
+pub struct DrawDebugPal {
+    inner: Box<dyn rtwins::pal::Pal>,
+}
+
+impl DrawDebugPal {
+    pub fn new(inner: Box<dyn rtwins::pal::Pal>) -> Self {
+        DrawDebugPal { inner }
+    }
+}
+
+impl rtwins::pal::Pal for DrawDebugPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        self.inner.write_char_n(c, repeat);
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.inner.write_str_n(s, repeat);
+    }
+
+    fn flush_buff(&mut self) {
+        self.inner.flush_buff();
+
+        if is_enabled() {
+            self.inner.sleep(FLUSH_DELAY_MS);
+        }
+    }
+
+    fn mark_logging(&mut self, active: bool) {
+        self.inner.mark_logging(active);
+    }
+
+    fn sleep(&self, ms: u16) {
+        self.inner.sleep(ms);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        self.inner.get_timestamp_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.inner.get_timespan_ms(prev_timestamp)
+    }
+}
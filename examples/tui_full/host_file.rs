@@ -0,0 +1,100 @@
+//! # Host file
+//!
+//! File on the host filesystem: `std::fs` on Linux, semihosting on Cortex-M;
+//! used for the input recording and the state persistence
+
+#[cfg(target_os = "none")]
+extern crate alloc;
+#[cfg(target_os = "none")]
+use alloc::string::String;
+#[cfg(target_os = "none")]
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// File on the host filesystem
+#[cfg(target_os = "linux")]
+pub struct HostFile(std::fs::File);
+
+/// Not all the data was written to the `HostFile`
+#[derive(Debug, PartialEq)]
+pub struct WriteError;
+
+#[cfg(target_os = "linux")]
+impl HostFile {
+    pub fn create(path: &str) -> Option<Self> {
+        std::fs::File::create(path).ok().map(HostFile)
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<(), WriteError> {
+        use std::io::Write;
+        self.0.write_all(data).map_err(|_| WriteError)
+    }
+
+    pub fn read_all(path: &str) -> Option<Vec<u8>> {
+        std::fs::read(path).ok()
+    }
+}
+
+/// File on the host filesystem, accessed with semihosting
+#[cfg(target_os = "none")]
+pub struct HostFile(isize);
+
+#[cfg(target_os = "none")]
+impl HostFile {
+    fn open(path: &str, mode: usize) -> isize {
+        let mut cpath = String::from(path);
+        cpath.push('\0');
+
+        unsafe {
+            cortex_m_semihosting::syscall!(OPEN, cpath.as_ptr(), mode, path.len()) as isize
+        }
+    }
+
+    pub fn create(path: &str) -> Option<Self> {
+        // binary mode - the data is written as it is, with no newline translation
+        let fd = Self::open(path, cortex_m_semihosting::nr::open::W_TRUNC_BINARY);
+        if fd == -1 {
+            None
+        }
+        else {
+            Some(HostFile(fd))
+        }
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<(), WriteError> {
+        // WRITE returns the number of bytes NOT written
+        let rc = unsafe {
+            cortex_m_semihosting::syscall!(WRITE, self.0, data.as_ptr(), data.len())
+        };
+
+        if rc == 0 {
+            Ok(())
+        }
+        else {
+            Err(WriteError)
+        }
+    }
+
+    pub fn read_all(path: &str) -> Option<Vec<u8>> {
+        let fd = Self::open(path, cortex_m_semihosting::nr::open::R_BINARY);
+        if fd == -1 {
+            return None;
+        }
+
+        let len = unsafe { cortex_m_semihosting::syscall!(FLEN, fd) } as isize;
+        let mut data = alloc::vec![0u8; len.max(0) as usize];
+        // READ returns the number of bytes NOT read
+        let rc = unsafe { cortex_m_semihosting::syscall!(READ, fd, data.as_mut_ptr(), data.len()) };
+        data.truncate(data.len().saturating_sub(rc));
+        unsafe { cortex_m_semihosting::syscall!(CLOSE, fd) };
+        Some(data)
+    }
+}
+
+#[cfg(target_os = "none")]
+impl Drop for HostFile {
+    fn drop(&mut self) {
+        unsafe { cortex_m_semihosting::syscall!(CLOSE, self.0) };
+    }
+}
@@ -0,0 +1,144 @@
+//! # Terminal size detection with the cursor position report
+//!
+//! Without the SIGWINCH, the only way to learn the terminal size is to move the cursor
+//! to the far bottom-right corner and ask the terminal where it has ended up
+
+use crate::input_source::{InputResult, InputSource};
+use rtwins::TERM;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Save the cursor, move it far away, request the position report, restore the cursor
+const CPR_QUERY: &str = "\x1b7\x1b[999;999H\x1b[6n\x1b8";
+
+/// How often the size is checked while there is no input
+const QUERY_INTERVAL_MS: u32 = 2000;
+
+/// Longest report: `ESC [ 65535 ; 65535 R`
+const CPR_MAX_LEN: usize = 14;
+
+/// Parses the `ESC [ rows ; cols R` report, returning (rows, cols)
+fn parse_cpr(seq: &[u8]) -> Option<(u16, u16)> {
+    let body = seq.strip_prefix(b"\x1b[")?.strip_suffix(b"R")?;
+    let body = core::str::from_utf8(body).ok()?;
+    let (rows, cols) = body.split_once(';')?;
+    Some((rows.parse().ok()?, cols.parse().ok()?))
+}
+
+/// Returns true if the `seq` is the beginning of the report, without the final `R`
+fn is_cpr_prefix(seq: &[u8]) -> bool {
+    seq.len() < CPR_MAX_LEN
+        && seq.starts_with(b"\x1b[")
+        && seq[2..].iter().all(|b| b.is_ascii_digit() || *b == b';')
+}
+
+/// State of the size queries
+#[derive(Default)]
+struct CprState {
+    /// query was sent, the report is expected
+    pending: bool,
+    last_query_at: u32,
+    /// last known size, (cols, rows)
+    size: (u16, u16),
+}
+
+impl CprState {
+    fn query_due(&self) -> bool {
+        TERM.try_lock().map_or(false, |term| {
+            self.size == (0, 0)
+                || term.pal.get_timespan_ms(self.last_query_at) >= QUERY_INTERVAL_MS
+        })
+    }
+
+    fn send_query(&mut self) {
+        if let Some(mut term_guard) = TERM.try_lock() {
+            term_guard.write_str(CPR_QUERY);
+            term_guard.flush_buff();
+            self.last_query_at = term_guard.pal.get_timestamp_ms();
+            self.pending = true;
+        }
+    }
+}
+
+/// Periodically queries the terminal size, reporting the changes as `InputResult::Resized`;
+/// the report split between the reads is joined
+pub struct InputCprResize {
+    src: Box<dyn InputSource>,
+    cpr: CprState,
+    /// beginning of the report, waiting for the rest
+    partial: Vec<u8>,
+    /// the partial report joined with the next read
+    joined: Vec<u8>,
+}
+
+impl InputCprResize {
+    /// Wraps the `src`; the first query is sent on the first input timeout
+    pub fn new(src: Box<dyn InputSource>) -> Self {
+        InputCprResize {
+            src,
+            cpr: CprState::default(),
+            partial: Vec::new(),
+            joined: Vec::new(),
+        }
+    }
+}
+
+impl InputSource for InputCprResize {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        let cpr = &mut self.cpr;
+
+        match self.src.read_input(timeout_ms) {
+            InputResult::Data(seq) => {
+                let seq: &[u8] = if self.partial.is_empty() {
+                    seq
+                }
+                else {
+                    self.joined.clear();
+                    self.joined.append(&mut self.partial);
+                    self.joined.extend_from_slice(seq);
+                    &self.joined
+                };
+
+                if cpr.pending && is_cpr_prefix(seq) {
+                    // the rest comes with the next read
+                    self.partial.extend_from_slice(seq);
+                    return InputResult::Timeout;
+                }
+
+                // C-F3 comes as `ESC [ 1 ; 5 R`, so only the pending report with more than 1 row
+                // is taken as the size
+                match parse_cpr(seq) {
+                    Some((rows, cols)) if cpr.pending && rows > 1 => {
+                        cpr.pending = false;
+
+                        if cpr.size != (cols, rows) {
+                            cpr.size = (cols, rows);
+                            InputResult::Resized { cols, rows }
+                        }
+                        else {
+                            InputResult::Timeout
+                        }
+                    }
+                    _ => InputResult::Data(seq),
+                }
+            }
+            InputResult::Timeout if !self.partial.is_empty() => {
+                // the report was not completed; pass on what has been received
+                self.joined.clear();
+                self.joined.append(&mut self.partial);
+                InputResult::Data(&self.joined)
+            }
+            InputResult::Timeout => {
+                if cpr.query_due() {
+                    cpr.send_query();
+                }
+                InputResult::Timeout
+            }
+            other => other,
+        }
+    }
+}
@@ -2,14 +2,130 @@
 
 use libc;
 
-use std::io::Read;
-use std::os::unix::io::AsRawFd;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::input_source::{self, InputError, InputResult, InputSource};
+use rtwins::TERM;
 
 // default reference to the controlling terminal for a process;
 // to read the input in GDB session, you may want to use another terminal:
 // https://stackoverflow.com/questions/8963208/gdb-display-output-of-target-application-in-a-separate-window#31804225
 const TTY_FILE_PATH: &str = "/dev/tty";
 
+/// Bytes read at once; reading continues while there are more available
+const READ_CHUNK_SIZE: usize = 256;
+
+/// Mouse reporting and bracketed paste off, default attributes, cursor visible
+const TERM_SANE_SEQ: &str = "\x1b[?1000l\x1b[?1002l\x1b[?1003l\x1b[?1006l\x1b[?2004l\x1b[0m\x1b[?25h";
+
+// flags set by the signal handler, consumed by the `read_input()`
+static SIG_STOP: AtomicBool = AtomicBool::new(false);
+static SIG_CONT: AtomicBool = AtomicBool::new(false);
+static SIG_QUIT: AtomicBool = AtomicBool::new(false);
+static SIG_WINCH: AtomicBool = AtomicBool::new(false);
+
+/// The tty to restore when the application panics: descriptor and original `c_lflag`
+static TTY_RESTORE: Mutex<Option<(RawFd, libc::tcflag_t)>> = Mutex::new(None);
+
+extern "C" fn on_signal(sig: libc::c_int) {
+    // only the async-signal-safe operations here
+    match sig {
+        libc::SIGTSTP => SIG_STOP.store(true, Ordering::SeqCst),
+        libc::SIGCONT => SIG_CONT.store(true, Ordering::SeqCst),
+        libc::SIGWINCH => SIG_WINCH.store(true, Ordering::SeqCst),
+        _ => SIG_QUIT.store(true, Ordering::SeqCst),
+    }
+}
+
+/// Installs the signal handlers and the panic hook; called once
+fn install_handlers() {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+
+    INSTALLED.call_once(|| {
+        for sig in [
+            libc::SIGTSTP,
+            libc::SIGCONT,
+            libc::SIGWINCH,
+            libc::SIGINT,
+            libc::SIGTERM,
+        ] {
+            unsafe {
+                libc::signal(sig, on_signal as libc::sighandler_t);
+            }
+        }
+
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // the TERM may be locked by the panicking code - write directly to the stdout
+            let mut out = std::io::stdout().lock();
+            let _ = out.write_all(TERM_SANE_SEQ.as_bytes());
+            let _ = out.write_all(b"\n");
+            let _ = out.flush();
+            drop(out);
+
+            if let Ok(tty_restore) = TTY_RESTORE.try_lock() {
+                if let Some((fd, c_lflag_bkp)) = *tty_restore {
+                    restore_mode(fd, c_lflag_bkp);
+                }
+            }
+
+            default_hook(info);
+        }));
+    });
+}
+
+/// Disables the line buffering and echo; returns the original `c_lflag` and `VEOF`
+fn set_raw_mode(fd: RawFd) -> Option<(libc::tcflag_t, libc::cc_t)> {
+    unsafe {
+        let mut tios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        if 0 == libc::tcgetattr(fd, tios.as_mut_ptr()) {
+            let tios_ref = tios.assume_init_mut();
+            let ret = (tios_ref.c_lflag, tios_ref.c_cc[libc::VEOF]);
+            tios_ref.c_lflag &= !(libc::ICANON | libc::ECHO);
+            libc::tcsetattr(fd, libc::TCSAFLUSH, tios.as_ptr());
+            Some(ret)
+        }
+        else {
+            None
+        }
+    }
+}
+
+/// Restores the original console settings, like ECHO
+fn restore_mode(fd: RawFd, c_lflag_bkp: libc::tcflag_t) -> bool {
+    unsafe {
+        let mut tios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        if 0 == libc::tcgetattr(fd, tios.as_mut_ptr()) {
+            let tios_ref = tios.assume_init_mut();
+            // when restoring, explicitly add previously removed attributes,
+            // in case the program was aborted not executing this code
+            tios_ref.c_lflag = c_lflag_bkp | libc::ICANON | libc::ECHO;
+            libc::tcsetattr(fd, libc::TCSAFLUSH, tios.as_ptr());
+            true
+        }
+        else {
+            false
+        }
+    }
+}
+
+/// Returns the terminal size as (columns, rows)
+fn get_size(fd: RawFd) -> Option<(u16, u16)> {
+    unsafe {
+        let mut ws = std::mem::MaybeUninit::<libc::winsize>::uninit();
+        if 0 == libc::ioctl(fd, libc::TIOCGWINSZ, ws.as_mut_ptr()) {
+            let ws = ws.assume_init();
+            Some((ws.ws_col, ws.ws_row))
+        }
+        else {
+            None
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------------------------- //
 
 pub struct InputTty {
@@ -17,37 +133,29 @@ pub struct InputTty {
     tty_file: Option<std::fs::File>,
     c_lflag_bkp: libc::tcflag_t,
     eof_code: libc::cc_t,
-    input_timeout_ms: u16,
-    input_buff: [u8; rtwins::esc::SEQ_MAX_LENGTH],
-    input_len: u8,
+    /// all the bytes available at once; grows as needed, eg. when a text is pasted
+    input_buff: Vec<u8>,
 }
 
 impl Drop for InputTty {
     /// Restore original console settings, like ECHO
     fn drop(&mut self) {
         if let Some(ref f) = self.tty_file {
-            unsafe {
-                let mut tios = std::mem::MaybeUninit::<libc::termios>::uninit();
-                if 0 == libc::tcgetattr(f.as_raw_fd(), tios.as_mut_ptr()) {
-                    let tios_ref = tios.assume_init_mut();
-                    // when restoring, explicitly add previously removed attributes,
-                    // in case the program was aborted not executing this code
-                    tios_ref.c_lflag = self.c_lflag_bkp | libc::ICANON | libc::ECHO;
-                    libc::tcsetattr(f.as_raw_fd(), libc::TCSAFLUSH, tios.as_ptr());
-                }
-                else {
-                    let e = std::io::Error::last_os_error();
-                    eprintln!("Cannot restore tty : {:?}", e.kind());
-                }
+            if let Ok(mut tty_restore) = TTY_RESTORE.lock() {
+                *tty_restore = None;
+            }
+
+            if !restore_mode(f.as_raw_fd(), self.c_lflag_bkp) {
+                let e = std::io::Error::last_os_error();
+                eprintln!("Cannot restore tty : {:?}", e.kind());
             }
         }
     }
 }
 
 impl InputTty {
-    /// Createas new TTY input reader with given timeout in [ms];
-    /// the timeout applies when calling `read_input()`
-    pub fn new(tty_path: Option<String>, timeout_ms: u16) -> Self {
+    /// Createas new TTY input reader
+    pub fn new(tty_path: Option<String>) -> Self {
         let tty_path = tty_path.map(|path| {
             if path.parse::<u32>().is_ok() {
                 // if only the pts number was passed
@@ -63,9 +171,7 @@ impl InputTty {
             tty_file: None,
             c_lflag_bkp: 0,
             eof_code: 0,
-            input_timeout_ms: timeout_ms,
-            input_buff: [0u8; rtwins::esc::SEQ_MAX_LENGTH],
-            input_len: 0,
+            input_buff: Vec::with_capacity(READ_CHUNK_SIZE),
         };
 
         itty.tty_file = match std::fs::File::open(&itty.tty_path) {
@@ -78,73 +184,89 @@ impl InputTty {
         };
 
         if let Some(ref f) = itty.tty_file {
-            unsafe {
-                let mut tios = std::mem::MaybeUninit::<libc::termios>::uninit();
-                if 0 == libc::tcgetattr(f.as_raw_fd(), tios.as_mut_ptr()) {
-                    let tios_ref = tios.assume_init_mut();
-                    itty.c_lflag_bkp = tios_ref.c_lflag;
-                    itty.eof_code = tios_ref.c_cc[libc::VEOF];
-                    tios_ref.c_lflag &= !(libc::ICANON | libc::ECHO);
-                    libc::tcsetattr(f.as_raw_fd(), libc::TCSAFLUSH, tios.as_ptr());
-                }
-                else {
-                    itty.tty_file = None;
-                    let e = std::io::Error::last_os_error();
-                    eprintln!("Cannot setup tty : {:?}", e.kind());
+            if let Some((c_lflag, eof_code)) = set_raw_mode(f.as_raw_fd()) {
+                itty.c_lflag_bkp = c_lflag;
+                itty.eof_code = eof_code;
+
+                if let Ok(mut tty_restore) = TTY_RESTORE.lock() {
+                    *tty_restore = Some((f.as_raw_fd(), c_lflag));
                 }
+
+                install_handlers();
+                // report the initial size with the first read
+                SIG_WINCH.store(true, Ordering::SeqCst);
+            }
+            else {
+                itty.tty_file = None;
+                let e = std::io::Error::last_os_error();
+                eprintln!("Cannot setup tty : {:?}", e.kind());
             }
         }
 
         itty
     }
 
-    /// Returns tuple with ESC sequence slice and bool marker set to true,
-    /// if application termination was requested (C-d)
-    pub fn read_input(&mut self) -> (&[u8], bool) {
-        for b in self.input_buff.iter_mut() {
-            *b = 0;
+    /// Returns the result for the signal received since the last call, if any
+    fn check_signals(&self, fd: RawFd) -> Option<InputResult<'static>> {
+        if SIG_QUIT.swap(false, Ordering::SeqCst) {
+            return Some(InputResult::Quit);
         }
 
-        if self.wait_and_read_input_sequence() {
-            let exit_requested = self.input_buff[0] == self.eof_code && self.input_buff[1] == 0;
-            return (&self.input_buff[..self.input_len as usize], exit_requested);
+        if SIG_STOP.swap(false, Ordering::SeqCst) {
+            self.suspend(fd);
+            return Some(InputResult::Resumed);
         }
 
-        (&self.input_buff[..0], false)
-    }
+        if SIG_CONT.swap(false, Ordering::SeqCst) {
+            // stopped and continued without the C-z, eg. by the `kill -STOP`
+            set_raw_mode(fd);
+            return Some(InputResult::Resumed);
+        }
 
-    fn wait_and_read_input_sequence(&mut self) -> bool {
-        if let Some(ref mut f) = self.tty_file {
-            if Self::wait_input(f.as_raw_fd(), self.input_timeout_ms) {
-                // read up to 8-1 bytes
-                let res = f.read(&mut self.input_buff[..rtwins::esc::SEQ_MAX_LENGTH - 1]);
-                if let Ok(nb) = res {
-                    // print!("nb={} ", nb);
-                    self.input_buff[nb] = 0;
-                    self.input_len = nb as u8;
-                    return true;
-                }
-                else {
-                    self.input_len = 0;
-                    self.input_buff[0] = 0;
-                }
+        if SIG_WINCH.swap(false, Ordering::SeqCst) {
+            if let Some((cols, rows)) = get_size(fd) {
+                return Some(InputResult::Resized { cols, rows });
             }
         }
 
-        false
+        None
+    }
+
+    /// Leaves the terminal in the original state and stops the process;
+    /// returns when the process is continued
+    fn suspend(&self, fd: RawFd) {
+        if let Some(mut term_guard) = TERM.try_lock() {
+            term_guard.mouse_mode(rtwins::MouseMode::Off);
+            term_guard.write_str(input_source::PASTE_MODE_OFF);
+            let logs_row = term_guard.trace_row;
+            term_guard.move_to(0, logs_row);
+            term_guard.flush_buff();
+        }
+
+        restore_mode(fd, self.c_lflag_bkp);
+
+        // SIGSTOP cannot be handled - the execution continues here after the SIGCONT
+        unsafe {
+            libc::raise(libc::SIGSTOP);
+        }
+
+        SIG_CONT.store(false, Ordering::SeqCst);
+        // the terminal could be resized in the meantime
+        SIG_WINCH.store(true, Ordering::SeqCst);
+        set_raw_mode(fd);
     }
 
-    fn wait_input(fd: std::os::unix::io::RawFd, key_timeout_ms: u16) -> bool {
+    fn wait_input(fd: RawFd, key_timeout_ms: u16) -> bool {
         unsafe {
             let mut read_set = std::mem::MaybeUninit::<libc::fd_set>::uninit();
             let ptr_read_set = read_set.as_mut_ptr();
             libc::FD_ZERO(ptr_read_set);
             libc::FD_SET(fd, ptr_read_set);
 
-            // wait for key
+            // wait for key; tv_usec above 1s is rejected by select()
             let mut tv = libc::timeval {
-                tv_sec: 0,
-                tv_usec: key_timeout_ms as i64 * 1000,
+                tv_sec: (key_timeout_ms / 1000) as libc::time_t,
+                tv_usec: (key_timeout_ms % 1000) as libc::suseconds_t * 1000,
             };
 
             //https://docs.rs/libc/0.2.112/libc/fn.select.html
@@ -160,3 +282,66 @@ impl InputTty {
         }
     }
 }
+
+impl InputSource for InputTty {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        let fd = match self.tty_file {
+            Some(ref f) => f.as_raw_fd(),
+            None => return InputResult::Error(InputError::NotAvailable),
+        };
+
+        self.input_buff.clear();
+
+        if let Some(res) = self.check_signals(fd) {
+            return res;
+        }
+
+        // select() is interrupted by the signals, so they are handled without delay
+        if !Self::wait_input(fd, timeout_ms) {
+            return self.check_signals(fd).unwrap_or(InputResult::Timeout);
+        }
+
+        let f = match self.tty_file {
+            Some(ref mut f) => f,
+            None => return InputResult::Error(InputError::NotAvailable),
+        };
+
+        // read everything that is available, so the long sequences are not split
+        loop {
+            let len = self.input_buff.len();
+            self.input_buff.resize(len + READ_CHUNK_SIZE, 0);
+
+            match f.read(&mut self.input_buff[len..]) {
+                Ok(nb) => {
+                    self.input_buff.truncate(len + nb);
+
+                    if nb < READ_CHUNK_SIZE || !Self::wait_input(fd, 0) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.input_buff.truncate(len);
+
+                    if len == 0 {
+                        let code = e.raw_os_error().unwrap_or(-1);
+                        return InputResult::Error(InputError::Read(code));
+                    }
+                    break;
+                }
+            }
+        }
+
+        let seq = &self.input_buff[..];
+
+        if seq.is_empty() {
+            // reported as readable, but nothing read: end of file, the tty is closed or hung up
+            InputResult::Quit
+        }
+        else if input_source::is_quit_seq(seq, self.eof_code) {
+            InputResult::Quit
+        }
+        else {
+            InputResult::Data(seq)
+        }
+    }
+}
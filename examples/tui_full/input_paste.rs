@@ -0,0 +1,193 @@
+//! # Bracketed paste
+//!
+//! With the `input_source::PASTE_MODE_ON`, the terminal wraps the pasted text in markers,
+//! so it can be told apart from the typed keys and delivered in one piece
+
+use crate::input_source::{InputResult, InputSource};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+const PASTE_BEGIN: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Longer pasted text is cut, to protect the heap
+const PASTE_MAX_LENGTH: usize = 4096;
+
+/// Paste is finished if the source sends nothing for so long, eg. the end marker was lost;
+/// the caller's timeout does not apply inside of the paste, as it may be even zero
+const PASTE_IDLE_TIMEOUT_MS: u16 = 1000;
+
+/// How long to wait for the rest of the split begin marker; the terminal sends it at once,
+/// and the lone Esc key should not be delayed
+const MARKER_REST_TIMEOUT_MS: u16 = 50;
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Returns the length of the `data` tail that is the beginning of the `marker`
+fn partial_marker_len(data: &[u8], marker: &[u8]) -> usize {
+    (1..marker.len())
+        .rev()
+        .find(|&n| data.ends_with(&marker[..n]))
+        .unwrap_or(0)
+}
+
+/// Returns the result not borrowing the source
+fn detach(res: InputResult<'_>) -> InputResult<'static> {
+    match res {
+        InputResult::Quit => InputResult::Quit,
+        InputResult::Resumed => InputResult::Resumed,
+        InputResult::Resized { cols, rows } => InputResult::Resized { cols, rows },
+        InputResult::Error(e) => InputResult::Error(e),
+        _ => InputResult::Timeout,
+    }
+}
+
+/// Passes the input from the source, collecting the pasted text into a single `InputResult::Paste`
+pub struct InputPaste {
+    src: Box<dyn InputSource>,
+    /// bytes read from the source, not returned yet
+    pending: Vec<u8>,
+    /// bytes returned by the last call
+    out: Vec<u8>,
+    /// pasted text collected so far; None if not inside of the paste markers
+    paste: Option<Vec<u8>>,
+    /// pasted text returned by the last call
+    paste_text: String,
+    /// result received while collecting the paste, to be returned by the next call
+    deferred: Option<InputResult<'static>>,
+}
+
+impl InputPaste {
+    pub fn new(src: Box<dyn InputSource>) -> Self {
+        InputPaste {
+            src,
+            pending: Vec::new(),
+            out: Vec::new(),
+            paste: None,
+            paste_text: String::new(),
+            deferred: None,
+        }
+    }
+
+    /// Appends the source data to the `pending`; other results are returned
+    fn read_src(&mut self, timeout_ms: u16) -> Option<InputResult<'static>> {
+        match self.src.read_input(timeout_ms) {
+            InputResult::Data(seq) => {
+                self.pending.extend_from_slice(seq);
+                None
+            }
+            res => Some(detach(res)),
+        }
+    }
+
+    /// Reads the source while collecting the paste; returns true if the paste is to be finished:
+    /// the source was closed, or sent nothing for the `PASTE_IDLE_TIMEOUT_MS`
+    fn read_paste_src(&mut self) -> bool {
+        match self.read_src(PASTE_IDLE_TIMEOUT_MS) {
+            None => false,
+            Some(InputResult::Timeout) => true,
+            Some(res @ (InputResult::Quit | InputResult::Error(_))) => {
+                self.deferred = Some(res);
+                true
+            }
+            // returned after the paste
+            Some(res) => {
+                self.deferred = Some(res);
+                false
+            }
+        }
+    }
+
+    fn finish_paste(&mut self) -> InputResult<'_> {
+        let mut paste = self.paste.take().unwrap_or_default();
+
+        if paste.len() > PASTE_MAX_LENGTH {
+            rtwins::tr_warn!("Pasted text cut to {} B", PASTE_MAX_LENGTH);
+            paste.truncate(PASTE_MAX_LENGTH);
+        }
+
+        self.paste_text = String::from_utf8_lossy(&paste).into_owned();
+        InputResult::Paste(&self.paste_text)
+    }
+}
+
+impl InputSource for InputPaste {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        if let Some(res) = self.deferred.take() {
+            return res;
+        }
+
+        loop {
+            if self.pending.is_empty() {
+                if self.paste.is_some() {
+                    if self.read_paste_src() {
+                        return self.finish_paste();
+                    }
+                }
+                else if let Some(res) = self.read_src(timeout_ms) {
+                    return res;
+                }
+            }
+
+            if let Some(ref mut paste) = self.paste {
+                if let Some(pos) = find(&self.pending, PASTE_END) {
+                    paste.extend_from_slice(&self.pending[..pos]);
+                    self.pending.drain(..pos + PASTE_END.len());
+                    return self.finish_paste();
+                }
+
+                // the end marker may be split between the reads
+                let keep = partial_marker_len(&self.pending, PASTE_END);
+                let take = self.pending.len() - keep;
+                paste.extend_from_slice(&self.pending[..take]);
+                self.pending.drain(..take);
+
+                if keep > 0 && self.read_paste_src() {
+                    let tail = core::mem::take(&mut self.pending);
+                    self.paste.get_or_insert_with(Vec::new).extend(tail);
+                    return self.finish_paste();
+                }
+                continue;
+            }
+
+            match find(&self.pending, PASTE_BEGIN) {
+                Some(0) => {
+                    self.pending.drain(..PASTE_BEGIN.len());
+                    self.paste = Some(Vec::new());
+                }
+                Some(pos) => {
+                    // keys typed before the paste
+                    self.out = self.pending.drain(..pos).collect();
+                    return InputResult::Data(&self.out);
+                }
+                None => {
+                    // the begin marker may be split between the reads
+                    let keep = partial_marker_len(&self.pending, PASTE_BEGIN);
+                    let take = self.pending.len() - keep;
+
+                    if take == 0 {
+                        if let Some(res) = self.read_src(MARKER_REST_TIMEOUT_MS) {
+                            // not the marker
+                            if !matches!(res, InputResult::Timeout) {
+                                self.deferred = Some(res);
+                            }
+                            self.out = core::mem::take(&mut self.pending);
+                            return InputResult::Data(&self.out);
+                        }
+                        continue;
+                    }
+
+                    self.out = self.pending.drain(..take).collect();
+                    return InputResult::Data(&self.out);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! # Input recording and deterministic replay
+//!
+//! Recording is a text file with one input sequence per line:
+//! `<timestamp_ms> <hex bytes>`, eg. `1520 1b5b41`
+
+use crate::host_file::HostFile;
+use crate::input_source::{InputResult, InputSource};
+use rtwins::TERM;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+fn timestamp_ms() -> u32 {
+    TERM.try_lock().map_or(0, |term| term.pal.get_timestamp_ms())
+}
+
+fn sleep_ms(ms: u16) {
+    if let Some(term) = TERM.try_lock() {
+        term.pal.sleep(ms);
+    }
+}
+
+/// Passes the input from the source, writing every sequence to the recording file
+pub struct InputRecorder {
+    src: Box<dyn InputSource>,
+    file: HostFile,
+    started_at: u32,
+}
+
+impl InputSource for InputRecorder {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        let res = self.src.read_input(timeout_ms);
+
+        if let InputResult::Data(seq) = res {
+            let mut line = format!("{} ", timestamp_ms().wrapping_sub(self.started_at));
+            for b in seq.iter() {
+                line.push_str(&format!("{:02x}", b));
+            }
+            line.push('\n');
+
+            if self.file.write(line.as_bytes()).is_err() {
+                rtwins::tr_warn!("Cannot write the recording");
+            }
+        }
+
+        res
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Single recorded input sequence
+struct RecEntry {
+    timestamp_ms: u32,
+    seq: Vec<u8>,
+}
+
+/// Feeds the recorded input back, keeping the original time gaps
+pub struct InputReplay {
+    entries: Vec<RecEntry>,
+    next_idx: usize,
+    /// 1 - original pace, 2 - two times faster..., 0 - no delays at all
+    speed: u16,
+    started_at: u32,
+    /// source used after the recording is over; if None, quit is requested
+    then: Option<Box<dyn InputSource>>,
+}
+
+impl InputReplay {
+    /// Parses the recording file content
+    fn new(data: &[u8], speed: u16, then: Option<Box<dyn InputSource>>) -> Self {
+        let text = String::from_utf8_lossy(data);
+        let entries: Vec<RecEntry> = text
+            .lines()
+            .enumerate()
+            .filter_map(|(nr, line)| {
+                let entry = Self::parse_line(line);
+                if entry.is_none() && !line.trim().is_empty() {
+                    rtwins::tr_warn!("Recording line {} ignored", nr + 1);
+                }
+                entry
+            })
+            .collect();
+
+        InputReplay {
+            entries,
+            next_idx: 0,
+            speed,
+            started_at: timestamp_ms(),
+            then,
+        }
+    }
+
+    fn parse_line(line: &str) -> Option<RecEntry> {
+        let (ts, hex) = line.trim().split_once(' ')?;
+        let timestamp_ms = ts.parse::<u32>().ok()?;
+
+        if hex.is_empty() || hex.len() % 2 != 0 {
+            return None;
+        }
+
+        let seq = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+
+        Some(RecEntry { timestamp_ms, seq })
+    }
+}
+
+impl InputSource for InputReplay {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        if self.next_idx >= self.entries.len() {
+            return match self.then {
+                Some(ref mut src) => src.read_input(timeout_ms),
+                None => InputResult::Quit,
+            };
+        }
+
+        let due_ms = match self.speed {
+            0 => 0,
+            speed => self.entries[self.next_idx].timestamp_ms / speed as u32,
+        };
+        let elapsed_ms = timestamp_ms().wrapping_sub(self.started_at);
+
+        if elapsed_ms < due_ms {
+            let wait_ms = due_ms - elapsed_ms;
+            sleep_ms(wait_ms.min(timeout_ms as u32) as u16);
+
+            if wait_ms > timeout_ms as u32 {
+                return InputResult::Timeout;
+            }
+        }
+
+        self.next_idx += 1;
+        InputResult::Data(&self.entries[self.next_idx - 1].seq)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Wraps the live input with the replay of `replay.0` file at `replay.1` speed,
+/// and/or with the recorder writing to the `record` file
+pub fn wrap_input(
+    live_inp: Box<dyn InputSource>,
+    replay: Option<(&str, u16)>,
+    record: Option<&str>,
+) -> Box<dyn InputSource> {
+    let mut inp = live_inp;
+
+    if let Some((path, speed)) = replay {
+        match HostFile::read_all(path) {
+            Some(data) => {
+                let replay = InputReplay::new(&data, speed, Some(inp));
+                rtwins::tr_info!("Replaying {} sequences from '{}'", replay.entries.len(), path);
+                inp = Box::new(replay);
+            }
+            None => rtwins::tr_err!("Cannot read recording '{}'", path),
+        }
+    }
+
+    if let Some(path) = record {
+        match HostFile::create(path) {
+            Some(file) => {
+                rtwins::tr_info!("Recording the input to '{}'", path);
+                inp = Box::new(InputRecorder {
+                    src: inp,
+                    file,
+                    started_at: timestamp_ms(),
+                });
+            }
+            None => rtwins::tr_err!("Cannot create recording '{}'", path),
+        }
+    }
+
+    inp
+}
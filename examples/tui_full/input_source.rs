@@ -0,0 +1,55 @@
+//! # RTWins input source abstraction
+//!
+//! Common contract of all the input readers (TTY, semihosting, UART...),
+//! so the main loop does not depend on the transport
+
+// shared by the examples, not every one uses all of it
+#![allow(dead_code)]
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Input reading failure reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    /// input device could not be opened
+    NotAvailable,
+    /// read operation failed; transport-specific error code
+    Read(i32),
+}
+
+/// Outcome of the `InputSource::read_input()`
+pub enum InputResult<'a> {
+    /// raw input bytes, to be decoded by the `input_decoder::Decoder`
+    Data(&'a [u8]),
+    /// no input within given timeout
+    Timeout,
+    /// application termination was requested (C-d, SIGINT, SIGTERM)
+    Quit,
+    /// application was continued after being stopped (C-z);
+    /// the terminal settings are restored, but the screen must be redrawn
+    Resumed,
+    /// text pasted in the bracketed paste mode, received as a whole
+    Paste(&'a str),
+    /// terminal size has changed
+    Resized { cols: u16, rows: u16 },
+    /// reading failed, the source is unusable
+    Error(InputError),
+}
+
+/// Source of the raw terminal input
+pub trait InputSource {
+    /// Waits up to `timeout_ms` [ms] for the input
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_>;
+}
+
+/// Bracketed paste mode: the pasted text comes between `ESC [ 200 ~` and `ESC [ 201 ~`
+pub const PASTE_MODE_ON: &str = "\x1b[?2004h";
+pub const PASTE_MODE_OFF: &str = "\x1b[?2004l";
+
+/// Ctrl-D, the default VEOF of the terminal
+pub const EOT_CODE: u8 = 0x04;
+
+/// Returns true if the sequence is a lone C-d
+pub fn is_quit_seq(seq: &[u8], eof_code: u8) -> bool {
+    seq.len() == 1 && seq[0] == eof_code
+}
@@ -0,0 +1,191 @@
+//! # Trace log file
+//!
+//! Plain text copy of the traces, with the size-based rotation:
+//! `logs.txt` -> `logs.txt.1` -> `logs.txt.2`...
+//!
+//! The traces are captured by the `LogPal` on top of the PALs stack,
+//! so the buffering or diffing PALs below, like the compositor, do not affect them
+
+use std::io::Write;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// File size [B] that triggers the rotation
+const LOG_MAX_SIZE: u64 = 1024 * 1024;
+/// Number of rotated files kept, besides the current one
+const LOG_KEEP_FILES: u8 = 3;
+
+pub struct LogFile {
+    path: String,
+    file: std::fs::File,
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens the log file, appending to the existing content
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let size = file.metadata().map_or(0, |md| md.len());
+
+        Ok(LogFile {
+            path: path.to_owned(),
+            file,
+            size,
+        })
+    }
+
+    /// Appends the terminal output, with all the escape sequences removed
+    pub fn write(&mut self, text: &str) {
+        let mut text = strip_ansi(text);
+        if text.is_empty() {
+            return;
+        }
+
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if self.size + text.len() as u64 > LOG_MAX_SIZE {
+            self.rotate();
+        }
+
+        if self.file.write_all(text.as_bytes()).is_ok() {
+            self.size += text.len() as u64;
+        }
+    }
+
+    fn rotate(&mut self) {
+        let _ = self.file.flush();
+
+        for n in (1..LOG_KEEP_FILES).rev() {
+            let _ = std::fs::rename(
+                format!("{}.{}", self.path, n),
+                format!("{}.{}", self.path, n + 1),
+            );
+        }
+        let _ = std::fs::rename(&self.path, format!("{}.1", self.path));
+
+        match std::fs::File::create(&self.path) {
+            Ok(f) => {
+                self.file = f;
+                self.size = 0;
+            }
+            Err(e) => eprintln!("Cannot rotate log '{}' : {:?}", self.path, e.kind()),
+        }
+    }
+}
+
+/// PAL wrapper copying the traces - the output written while the logging is marked -
+/// to the log file
+pub struct LogPal {
+    inner: Box<dyn rtwins::pal::Pal>,
+    writing_logs: bool,
+    /// traces written since the logging started
+    log_buff: String,
+    log_file: LogFile,
+}
+
+impl LogPal {
+    pub fn new(inner: Box<dyn rtwins::pal::Pal>, log_file: LogFile) -> Self {
+        LogPal {
+            inner,
+            writing_logs: false,
+            log_buff: String::new(),
+            log_file,
+        }
+    }
+}
+
+impl rtwins::pal::Pal for LogPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        if self.writing_logs {
+            for _ in 0..repeat {
+                self.log_buff.push(c);
+            }
+        }
+
+        self.inner.write_char_n(c, repeat);
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        if self.writing_logs {
+            for _ in 0..repeat {
+                self.log_buff.push_str(s);
+            }
+        }
+
+        self.inner.write_str_n(s, repeat);
+    }
+
+    fn flush_buff(&mut self) {
+        self.inner.flush_buff();
+    }
+
+    fn mark_logging(&mut self, active: bool) {
+        if self.writing_logs && !active {
+            self.log_file.write(&self.log_buff);
+            self.log_buff.clear();
+        }
+
+        self.writing_logs = active;
+        self.inner.mark_logging(active);
+    }
+
+    fn sleep(&self, ms: u16) {
+        self.inner.sleep(ms);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        self.inner.get_timestamp_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.inner.get_timespan_ms(prev_timestamp)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Removes the CSI, OSC and other escape sequences, and the carriage returns;
+/// cursor positioning becomes a line break
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut it = s.chars().peekable();
+
+    while let Some(c) = it.next() {
+        match c {
+            '\x1b' => match it.next() {
+                // CSI: parameters, then the final byte in range @..~
+                Some('[') => {
+                    let fin = it.by_ref().find(|c| ('@'..='~').contains(c));
+                    // the traces are placed on the rows with cursor moves
+                    let line_open = !out.is_empty() && !out.ends_with('\n');
+                    if line_open && matches!(fin, Some('H') | Some('f')) {
+                        out.push('\n');
+                    }
+                }
+                // OSC: terminated with BEL or ST
+                Some(']') => {
+                    while let Some(c) = it.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && it.peek() == Some(&'\\') {
+                            it.next();
+                            break;
+                        }
+                    }
+                }
+                // two-character sequences, like ESC 7
+                _ => {}
+            },
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
@@ -8,145 +8,404 @@ use rtwins::wnd_manager::WindowManager;
 use rtwins::{tetrary, wgt, TERM};
 
 // use core::prelude::rust_2021::*;
-use core::cell::RefCell;
 
 extern crate alloc;
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::format;
-use alloc::rc::Rc;
-use alloc::vec;
-use alloc::vec::Vec;
+use alloc::string::String;
 
-use crate::tui_commands::Command;
+use crate::input_source::{InputResult, InputSource};
+use crate::tui_commands::{Command, DialogToken};
+use crate::tui_keybindings::{Action, KeyBindings};
 use crate::tui_main_def::id;
+use crate::tui_wnd_mngr::WndMngr;
 
 // https://doc.rust-lang.org/cargo/guide/project-layout.html
+mod clock;
+mod draw_debug;
+mod host_file;
+mod input_paste;
+mod input_record;
+mod input_source;
+mod pal_stats;
 mod tui_colors;
 mod tui_commands;
 mod tui_main_def;
+mod tui_help_def;
+mod tui_help_state;
+mod tui_inputdlg_def;
+mod tui_inputdlg_state;
+mod tui_keybindings;
 mod tui_main_state;
 mod tui_msgbox_def;
 mod tui_msgbox_state;
+mod tui_persist;
+mod tui_timers;
+mod tui_toast_def;
+mod tui_toast_state;
+mod tui_wnd_mngr;
 
 #[cfg(target_os = "linux")]
 mod input_libc_tty;
 #[cfg(target_os = "linux")]
+mod log_file;
+#[cfg(target_os = "linux")]
 mod pal_std;
+#[cfg(target_os = "linux")]
+mod pal_tcp;
+#[cfg(target_os = "linux")]
+mod pal_vscreen;
+#[cfg(any(target_os = "linux", feature = "compositor"))]
+mod vscreen;
+#[cfg(any(feature = "compositor", test))]
+mod pal_compositor;
 
 #[cfg(target_os = "none")]
+mod input_cpr;
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 mod pal_semihosting;
+// under QEMU, also the semihosting console input
+#[cfg(all(target_os = "none", any(feature = "uart", feature = "qemu")))]
+#[cfg_attr(not(feature = "uart"), allow(dead_code))]
+mod pal_uart;
 #[cfg(target_os = "none")]
 use alloc_cortex_m::CortexMHeap;
 #[cfg(target_os = "none")]
 use cortex_m_rt::entry;
-#[cfg(target_os = "none")]
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 use cortex_m_semihosting::debug;
-#[cfg(target_os = "none")]
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 use panic_semihosting as _;
+// no semihosting host to report to
+#[cfg(all(target_os = "none", feature = "uart"))]
+use panic_halt as _;
 
 // ---------------------------------------------------------------------------------------------- //
 
-struct WndMngr {
-    cmdque: Rc<RefCell<tui_commands::CommandsQueue>>,
-    visible: Vec<WId>,
-    main: tui_main_state::MainWndState,
-    msgbox: tui_msgbox_state::MsgBoxState,
+/// Ids of the application windows, assigned when registered in the `WndMngr`
+#[derive(Clone, Copy)]
+struct AppWnds {
+    main: WId,
+    /// notifications overlay
+    toast: WId,
 }
 
-impl WndMngr {
-    // rtwins::generate_ids!() - starts from 1, but Window ids are indexes and should start from 0
-    pub const MAIN: rtwins::wgt::WId = 0;
-    pub const MSGBOX: rtwins::wgt::WId = 1;
+impl AppWnds {
+    /// Registers all the application windows
+    fn create(wmngr: &mut WndMngr) -> Self {
+        let main = wmngr.add(|wnd_id, cmdque| {
+            Box::new(tui_main_state::MainWndState::new(
+                wnd_id,
+                &tui_main_def::WND_MAIN_WGTS[..],
+                cmdque,
+            ))
+        });
 
-    fn new() -> Self {
-        let cmdque = Rc::new(RefCell::new(tui_commands::CommandsQueue::default()));
+        let toast = wmngr.add(|wnd_id, cmdque| {
+            let mut toast = tui_toast_state::ToastState::new(
+                wnd_id,
+                &tui_toast_def::WND_TOAST_WGTS[..],
+                cmdque,
+            );
+            toast.anchor_on(&tui_main_def::WND_MAIN_WGTS[0]);
+            Box::new(toast)
+        });
 
-        let mut ret = Self {
-            cmdque: Rc::clone(&cmdque),
-            visible: vec![],
-            // all UI windows:
-            main: tui_main_state::MainWndState::new(
-                Self::MAIN,
-                &tui_main_def::WND_MAIN_WGTS[..],
-                Rc::clone(&cmdque),
-            ),
-            msgbox: tui_msgbox_state::MsgBoxState::new(
-                Self::MSGBOX,
-                &tui_msgbox_def::WND_MSGBOX_WGTS[..],
-                Rc::clone(&cmdque),
-            ),
-        };
+        AppWnds { main, toast }
+    }
 
-        ret.msgbox
-            .center_on(ret.main.get_widgets().first().unwrap());
-        ret
+    fn main_wnd<'a>(&self, wmngr: &'a mut WndMngr) -> &'a mut tui_main_state::MainWndState {
+        wmngr.get_as_mut(self.main).expect("main window not registered")
+    }
+
+    fn toast_wnd<'a>(&self, wmngr: &'a mut WndMngr) -> &'a mut tui_toast_state::ToastState {
+        wmngr.get_as_mut(self.toast).expect("toast window not registered")
     }
 }
 
-/*
-/// Iterator over manager windows
-struct WndMngrIter<'a> {
-    wm: &'a WndMngr,
-    iter_idx: usize,
+/// Returns the number of the popups over the main window
+fn popup_stack_level(wmngr: &WndMngr) -> u8 {
+    // the main window is the bottom one
+    wmngr.get_visible().len().saturating_sub(1) as u8
 }
 
-impl<'a> WndMngrIter<'a> {
-    fn new(wm: &'a WndMngr) -> Self {
-        WndMngrIter{
-            wm,
-            iter_idx: 0
-        }
-    }
+/// Creates and shows the message box on top of the visible windows; returns its id
+fn open_msgbox(
+    wmngr: &mut WndMngr,
+    title: String,
+    message: String,
+    buttons: &'static str,
+    owner: Option<WId>,
+    token: DialogToken,
+) -> WId {
+    let level = popup_stack_level(wmngr);
+
+    let wnd_id = wmngr.add(|wnd_id, cmdque| {
+        let mut msgbox = tui_msgbox_state::MsgBoxState::new(
+            wnd_id,
+            &tui_msgbox_def::WND_MSGBOX_WGTS[..],
+            cmdque,
+        );
+        msgbox.setup(title, message, buttons, owner, token);
+        msgbox.placement.set_stack_level(level);
+        msgbox.placement.center_on(&tui_main_def::WND_MAIN_WGTS[0]);
+        Box::new(msgbox)
+    });
+
+    wmngr.show(wnd_id);
+    wnd_id
 }
 
-impl<'a> Iterator for WndMngrIter<'a> {
-    type Item = &'a dyn WindowState;
+/// Creates and shows the input dialog on top of the visible windows; returns its id
+fn open_inputdlg(
+    wmngr: &mut WndMngr,
+    title: String,
+    prompt: String,
+    value: String,
+    numeric: Option<(i64, i64)>,
+    owner: Option<WId>,
+    token: DialogToken,
+) -> WId {
+    let level = popup_stack_level(wmngr);
+
+    let wnd_id = wmngr.add(|wnd_id, cmdque| {
+        let mut dlg = tui_inputdlg_state::InputDlgState::new(
+            wnd_id,
+            &tui_inputdlg_def::WND_INPUTDLG_WGTS[..],
+            cmdque,
+        );
+        dlg.setup(title, prompt, value, numeric, owner, token);
+        dlg.placement.set_stack_level(level);
+        dlg.placement.center_on(&tui_main_def::WND_MAIN_WGTS[0]);
+        Box::new(dlg)
+    });
+
+    wmngr.show(wnd_id);
+    wnd_id
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let result= match self.iter_idx {
-            0 => Some(&self.wm.main as &dyn WindowState),
-            1 => Some(&self.wm.msgbox as &dyn WindowState),
-            _ => None,
-        };
+/// Shows the help popup, listing the active shortcuts, unless it is already the top window
+fn open_help(wmngr: &mut WndMngr, keybindings: &KeyBindings) {
+    let top_is_help = match wmngr.get_visible().last() {
+        Some(top) => wmngr.get_as::<tui_help_state::HelpState>(*top).is_some(),
+        None => false,
+    };
 
-        self.iter_idx += 1;
-        result
+    if top_is_help {
+        return;
     }
+
+    let wnd_id = wmngr.add(|wnd_id, cmdque| {
+        let mut help =
+            tui_help_state::HelpState::new(wnd_id, &tui_help_def::WND_HELP_WGTS[..], cmdque);
+        help.setup(keybindings);
+        help.placement.center_on(&tui_main_def::WND_MAIN_WGTS[0]);
+        Box::new(help)
+    });
+
+    wmngr.show(wnd_id);
 }
-*/
 
-// impl<'a> WindowManager<WndMngrIter<'a>> for WndMngr {
-impl WindowManager for WndMngr {
-    fn get_ref(&self, wnd_id: WId) -> Option<&dyn WindowState> {
-        match wnd_id {
-            WndMngr::MAIN => Some(&self.main),
-            WndMngr::MSGBOX => Some(&self.msgbox),
-            _ => None,
-        }
+/// Removes the expired notifications; the overlay is hidden when none is left
+fn expire_toasts(wmngr: &mut WndMngr, wnds: &AppWnds, now: u32) {
+    if !wmngr.is_overlay_visible(wnds.toast) {
+        return;
     }
 
-    fn get_mut(&mut self, wnd_id: WId) -> Option<&mut dyn WindowState> {
-        match wnd_id {
-            WndMngr::MAIN => Some(&mut self.main),
-            WndMngr::MSGBOX => Some(&mut self.msgbox),
-            _ => None,
+    let toast = wnds.toast_wnd(wmngr);
+
+    if toast.expire(now) {
+        if toast.is_empty() {
+            wmngr.hide_overlay(wnds.toast);
+        }
+        else {
+            // the overlay shrinks; the area it no longer covers is restored
+            wmngr.update_overlays();
         }
     }
+}
 
-    #[inline]
-    fn get_visible(&self) -> &[WId] {
-        &self.visible[..]
+/// Notifies the windows about their expired timers, and redraws the top one
+fn run_timers(wmngr: &mut WndMngr, wnds: &AppWnds, now: u32) {
+    let expired = wmngr.timers.take_expired(now);
+
+    if expired.is_empty() {
+        return;
     }
 
-    #[inline]
-    fn get_visible_mut(&mut self) -> &mut Vec<WId> {
-        &mut self.visible
+    for (owner, token) in expired.into_iter() {
+        if let Some(wnd) = wmngr.get_wnd_mut(owner) {
+            wnd.on_timer(token);
+        }
     }
 
-    // fn iter(&self) -> WndMngrIter<'a> {
-    //     WndMngrIter::new(self)
-    // }
+    run_commands(wmngr, wnds);
+    // windows below are redrawn when uncovered
+    wmngr.draw_top_invalidated();
+}
+
+/// Returns the PAL timestamp [ms]; the timers and the toasts expiry depend on it,
+/// so on Cortex-M the SysTick clock must be started with `clock::init()` first
+fn timestamp_ms() -> u32 {
+    TERM.try_lock().map_or(0, |term| term.pal.get_timestamp_ms())
+}
+
+/// Runs the commands queued by the windows, including the ones queued meanwhile
+fn run_commands(wmngr: &mut WndMngr, wnds: &AppWnds) {
+    loop {
+        let cmdque = wmngr.cmdque.borrow_mut().take_commands();
+
+        let cmdque = match cmdque {
+            Some(cmdque) => cmdque,
+            None => break,
+        };
+
+        for cmd in cmdque.into_iter() {
+            match cmd {
+                Command::ShowPopup {
+                    title,
+                    message,
+                    buttons,
+                    owner,
+                    token,
+                } => {
+                    rtwins::tr_info!("Command: ShowPopup");
+                    open_msgbox(wmngr, title, message, buttons, owner, token);
+                }
+                Command::ShowInputDialog {
+                    title,
+                    prompt,
+                    value,
+                    numeric,
+                    owner,
+                    token,
+                } => {
+                    rtwins::tr_info!("Command: ShowInputDialog");
+                    open_inputdlg(wmngr, title, prompt, value, numeric, owner, token);
+                }
+                Command::ShowToast { severity, message } => {
+                    rtwins::tr_info!("Command: ShowToast");
+                    if !TIMERS_ENABLED {
+                        // would not disappear until the key is pressed
+                        rtwins::tr_warn!("Toast: {}", message);
+                        continue;
+                    }
+                    let now = timestamp_ms();
+                    wnds.toast_wnd(wmngr).push(severity, message, now);
+                    wmngr.show_overlay(wnds.toast);
+                    // wake up the main loop to hide it
+                    let duration_ms = tui_toast_state::TOAST_DURATION_MS;
+                    wmngr.timers.start(wnds.toast, 0, duration_ms, false, now);
+                }
+                Command::StartTimer {
+                    owner,
+                    token,
+                    interval_ms,
+                    periodic,
+                } => {
+                    if TIMERS_ENABLED {
+                        let now = timestamp_ms();
+                        wmngr.timers.start(owner, token, interval_ms, periodic, now);
+                    }
+                }
+                Command::StopTimer { owner, token } => {
+                    wmngr.timers.stop(owner, token);
+                }
+                Command::HidePopup { wnd_id } => {
+                    rtwins::tr_info!("Command: HidePopup");
+                    // the windows below are repainted
+                    wmngr.remove(wnd_id);
+                }
+                Command::DialogResult {
+                    owner,
+                    token,
+                    result,
+                } => {
+                    rtwins::tr_info!("Command: DialogResult");
+                    if let Some(wnd) = owner.and_then(|wnd_id| wmngr.get_wnd_mut(wnd_id)) {
+                        wnd.on_dialog_result(token, result);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the first row of the logs: below the main window, but still on the screen
+fn trace_row(main_ws: &mut dyn WindowState, rows: u16) -> u16 {
+    let coord = main_ws.get_window_coord();
+    let sz = main_ws.get_window_size();
+    let below_main = coord.row as u16 + sz.height as u16 + 1;
+    below_main.min(rows.saturating_sub(TRACE_ROWS_MIN))
+}
+
+/// How long the main loop waits for the input, when no timer is due sooner
+const INPUT_TIMEOUT_MS: u16 = 1000;
+
+/// The timers need the input returning on the timeout;
+/// the semihosting input on the hardware blocks until the key is pressed
+#[cfg(all(target_os = "none", not(feature = "uart")))]
+const TIMERS_ENABLED: bool = pal_semihosting::INPUT_TIMEOUT_HONOURED;
+#[cfg(not(all(target_os = "none", not(feature = "uart"))))]
+const TIMERS_ENABLED: bool = true;
+
+/// Logs area height kept visible when the terminal is too short for the main window
+const TRACE_ROWS_MIN: u16 = 4;
+
+/// Input recording file on the semihosting host
+#[cfg(target_os = "none")]
+const INPUT_REC_FILE: &str = "input.rec";
+
+/// Window state file on the semihosting host
+#[cfg(target_os = "none")]
+const STATE_FILE: &str = "tui_state.bin";
+
+/// Initial size of the compositor screen model, until the terminal reports the real one
+#[cfg(feature = "compositor")]
+const COMPOSITOR_SIZE: (u16, u16) = (90, 24);
+
+/// Wraps the PAL with the draw debugging, the output statistics, and the output compositor
+/// if enabled; the statistics count the bytes actually sent to the terminal
+fn wrap_pal(pal: Box<dyn rtwins::pal::Pal>) -> Box<dyn rtwins::pal::Pal> {
+    let pal: Box<dyn rtwins::pal::Pal> = Box::new(draw_debug::DrawDebugPal::new(pal));
+    let pal: Box<dyn rtwins::pal::Pal> = Box::new(pal_stats::StatsPal::new(pal));
+
+    #[cfg(feature = "compositor")]
+    let pal: Box<dyn rtwins::pal::Pal> = {
+        let (cols, rows) = COMPOSITOR_SIZE;
+        Box::new(pal_compositor::CompositorPal::new(pal, cols, rows))
+    };
+
+    pal
+}
+
+/// Returns the value of `--name=value` command line argument
+#[cfg(target_os = "linux")]
+fn cmdline_arg(prefix: &str) -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(|val| val.to_owned()))
+}
+
+/// Waits for the telnet client on the `port`
+#[cfg(target_os = "linux")]
+fn accept_tcp_client(port: &str) -> Option<(pal_tcp::TcpPal, pal_tcp::InputTcp)> {
+    let server = match port.parse::<u16>().map(pal_tcp::TcpServer::bind) {
+        Ok(Ok(server)) => server,
+        _ => {
+            eprintln!("Cannot listen on port '{}'", port);
+            return None;
+        }
+    };
+
+    println!("Waiting for the client: telnet localhost {}", server.local_port());
+
+    match server.accept() {
+        Ok(session) => Some(session),
+        Err(e) => {
+            eprintln!("Cannot accept the client : {:?}", e.kind());
+            None
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------------------------- //
@@ -166,20 +425,30 @@ fn main() {
 fn main() -> ! {
     tui();
 
-    if cfg!(feature = "qemu") {
+    #[cfg(all(feature = "qemu", not(feature = "uart")))]
+    {
         // exit QEMU
         // NOTE do not run this on hardware; it can corrupt OpenOCD state
         debug::exit(debug::EXIT_SUCCESS);
     }
 
-    loop {}
+    // exit QEMU started with `-no-reboot`, as the semihosting is not enabled
+    #[cfg(all(feature = "qemu", feature = "uart"))]
+    cortex_m::peripheral::SCB::sys_reset();
+
+    #[cfg(not(all(feature = "qemu", feature = "uart")))]
+    loop {};
 }
 
 fn tui() {
     // Initialize the allocator BEFORE you use it
     #[cfg(target_os = "none")]
     unsafe {
+        #[cfg(not(feature = "compositor"))]
         const HEAP_SIZE: usize = 1024 * 20; // in bytes
+        // the compositor screen model takes ~35kB
+        #[cfg(feature = "compositor")]
+        const HEAP_SIZE: usize = 1024 * 52; // in bytes
         ALLOCATOR.init(cortex_m_rt::heap_start() as usize, HEAP_SIZE);
     }
 
@@ -187,41 +456,83 @@ fn tui() {
     let cp = cortex_m::Peripherals::take().unwrap();
 
     #[cfg(target_os = "none")]
+    clock::init(cp.SYST);
+
+    #[cfg(all(target_os = "none", not(feature = "uart")))]
     {
-        let delay = cortex_m::delay::Delay::new(cp.SYST, 32_000_000);
-        TERM.try_lock().unwrap().pal = Box::new(pal_semihosting::SemihostingPal::new(delay));
+        let pal = Box::new(pal_semihosting::SemihostingPal::new());
+        TERM.try_lock().unwrap().pal = wrap_pal(pal);
     }
 
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "none", feature = "uart"))]
     {
-        // replace default PAL with our own:
-        TERM.try_lock().unwrap().pal = Box::new(pal_std::DemoPal::new());
-
-        // register function providing traces timestamp
-        rtwins::tr_set_timestr_function!(|| {
-            let local_time = chrono::Local::now();
-            local_time.format("%H:%M:%S%.3f ").to_string()
-        });
+        let pal = Box::new(pal_uart::UartPal::new());
+        TERM.try_lock().unwrap().pal = wrap_pal(pal);
     }
 
+    // register function providing traces timestamp
+    rtwins::tr_set_timestr_function!(|| clock::now().time_str());
+
+    #[cfg(target_os = "linux")]
+    let tcp_inp = {
+        // --tcp=2323 : serve the UI to the telnet client instead of this terminal
+        let tcp_session = cmdline_arg("--tcp=").and_then(|port| accept_tcp_client(&port));
+
+        let tcp_inp = match tcp_session {
+            Some((pal, inp)) => {
+                TERM.try_lock().unwrap().pal = wrap_pal(Box::new(pal));
+                Some(inp)
+            }
+            None => {
+                // replace default PAL with our own:
+                let mut pal = wrap_pal(Box::new(pal_std::DemoPal::new()));
+
+                // --log=logs.txt
+                if let Some(path) = cmdline_arg("--log=") {
+                    match log_file::LogFile::open(&path) {
+                        Ok(log_file) => pal = Box::new(log_file::LogPal::new(pal, log_file)),
+                        Err(e) => eprintln!("Cannot open log '{}' : {:?}", path, e.kind()),
+                    }
+                }
+
+                TERM.try_lock().unwrap().pal = pal;
+                None
+            }
+        };
+
+        tcp_inp
+    };
+
     // window manager and all windows
     let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
 
     // configure terminal
     if let Some(mut term_guard) = TERM.try_lock() {
-        term_guard.trace_row = {
-            let coord = wmngr.main.get_window_coord();
-            let sz = wmngr.main.get_window_size();
-            coord.row as u16 + sz.height as u16 + 1
-            // 1
-        };
+        term_guard.trace_row = trace_row(wnds.main_wnd(&mut wmngr), u16::MAX);
         term_guard.write_str(rtwins::esc::TERM_RESET);
+        term_guard.write_str(input_source::PASTE_MODE_ON);
         term_guard.mouse_mode(rtwins::MouseMode::M2);
     }
 
+    // --state=tui_state.bin : keep the main window state between the runs
+    #[cfg(target_os = "linux")]
+    let state_storage = cmdline_arg("--state=").map(tui_persist::Storage::File);
+    #[cfg(all(target_os = "none", not(feature = "persist_ram")))]
+    let state_storage = Some(tui_persist::Storage::File(STATE_FILE.to_owned()));
+    #[cfg(all(target_os = "none", feature = "persist_ram"))]
+    let state_storage = Some(tui_persist::Storage::Ram);
+
+    if let Some(blob) = state_storage.as_ref().and_then(|st| st.load()) {
+        match wnds.main_wnd(&mut wmngr).restore_state(&blob) {
+            Ok(()) => rtwins::tr_info!("State restored, {} B", blob.len()),
+            Err(e) => rtwins::tr_warn!("Saved state dropped: {:?}", e),
+        }
+    }
+
     tui_colors::init();
     // first draw of the UI
-    wmngr.show(WndMngr::MAIN);
+    wmngr.show(wnds.main);
 
     rtwins::tr_info!("Press Ctrl-D to quit");
     rtwins::tr_info!(
@@ -238,48 +549,148 @@ fn tui() {
     rtwins::tr_flush!(&mut TERM.try_lock().unwrap());
 
     #[cfg(target_os = "linux")]
-    let mut inp = {
-        let tty_path = {
-            // type `tty` in separate terminal, to get it's number
-            let path_opt = std::env::args().find(|a| a.starts_with("--tty=")).map(|tty|
-                // --tty=/dev/pts/10
-                // --tty=10
-                tty.split_once('=')
-                .unwrap_or_default().1.to_owned());
-
-            if let Some(ref p) = path_opt {
+    let inp: Box<dyn InputSource> = match tcp_inp {
+        Some(inp) => Box::new(inp),
+        None => {
+            // type `tty` in separate terminal, to get it's number:
+            // --tty=/dev/pts/10
+            // --tty=10
+            let tty_path = cmdline_arg("--tty=");
+
+            if let Some(ref p) = tty_path {
                 rtwins::tr_info!("Input TTY: {}", p);
             }
 
-            path_opt
-        };
+            Box::new(input_libc_tty::InputTty::new(tty_path))
+        }
+    };
+    #[cfg(all(target_os = "none", not(feature = "uart")))]
+    let inp: Box<dyn InputSource> = Box::new(pal_semihosting::InputSemiHost::new());
+    if !TIMERS_ENABLED {
+        rtwins::tr_warn!("Input blocks until a key is pressed; timers and toasts disabled");
+    }
+    #[cfg(all(target_os = "none", feature = "uart"))]
+    let inp: Box<dyn InputSource> = Box::new(pal_uart::InputUart::new());
 
-        input_libc_tty::InputTty::new(tty_path, 1000)
+    // --replay=input.rec --replay-speed=4 --record=input2.rec
+    #[cfg(target_os = "linux")]
+    let inp = {
+        let replay_path = cmdline_arg("--replay=");
+        let replay_speed = cmdline_arg("--replay-speed=").map_or(1, |s| s.parse().unwrap_or(1));
+        let rec_path = cmdline_arg("--record=");
+
+        input_record::wrap_input(
+            inp,
+            replay_path.as_deref().map(|p| (p, replay_speed)),
+            rec_path.as_deref(),
+        )
     };
     #[cfg(target_os = "none")]
-    let mut inp = pal_semihosting::InputSemiHost::new();
+    let inp = input_record::wrap_input(
+        Box::new(input_cpr::InputCprResize::new(inp)),
+        cfg!(feature = "input_replay").then_some((INPUT_REC_FILE, 1)),
+        cfg!(feature = "input_rec").then_some(INPUT_REC_FILE),
+    );
+
+    // the raw input is recorded, so the pasted text is collected at the end
+    let mut inp = input_paste::InputPaste::new(inp);
+
+    // --keys=keys.conf : override the global shortcuts
+    #[cfg(target_os = "linux")]
+    let keybindings = {
+        let mut keybindings = KeyBindings::new();
+
+        if let Some(path) = cmdline_arg("--keys=") {
+            match std::fs::read_to_string(&path).map(|conf| keybindings.load(&conf)) {
+                Ok(Ok(())) => rtwins::tr_info!("Key bindings: {}", path),
+                Ok(Err(e)) => rtwins::tr_err!("Key bindings '{}' {}", path, e),
+                Err(e) => rtwins::tr_err!("Cannot read '{}' : {:?}", path, e.kind()),
+            }
+        }
+
+        keybindings
+    };
+    #[cfg(target_os = "none")]
+    let keybindings = KeyBindings::new();
 
     let mut ique = rtwins::input_decoder::InputQue::new();
     let mut dec = rtwins::input_decoder::Decoder::default();
     let mut ii = rtwins::input::InputInfo::default();
     let mut mouse_on = true;
 
+    // timers started by the windows when created
+    run_commands(&mut wmngr, &wnds);
+
     #[allow(unused_labels)]
     'mainloop: loop {
-        let (inp_seq, q) = inp.read_input();
-        // TODO: detect that application was sent to background and restore terminal config
+        // wake up for the nearest timer
+        let timeout_ms = wmngr
+            .timers
+            .next_timeout_ms(timestamp_ms())
+            .map_or(INPUT_TIMEOUT_MS, |ms| ms.min(INPUT_TIMEOUT_MS as u32) as u16);
+        let inp_res = inp.read_input(timeout_ms);
+        // the waiting for the input is not a part of the frame
+        pal_stats::frame_begin();
+
+        let inp_seq = match inp_res {
+            InputResult::Data(seq) => seq,
+            InputResult::Timeout => &[],
+            InputResult::Quit => {
+                rtwins::tr_warn!("Exit requested");
+                break;
+            }
+            InputResult::Resumed => {
+                rtwins::tr_info!("Resumed");
+                {
+                    let mut term_guard = TERM.try_lock().unwrap();
+                    term_guard.write_str(input_source::PASTE_MODE_ON);
+                    term_guard.mouse_mode(tetrary!(
+                        mouse_on,
+                        rtwins::MouseMode::M2,
+                        rtwins::MouseMode::Off
+                    ));
+                    term_guard.screen_clr_all();
+                }
+                wmngr.draw_all();
+                &[]
+            }
+            InputResult::Paste(text) => {
+                rtwins::tr_debug!("Pasted {} B", text.len());
+                if wmngr.is_top(wnds.main) {
+                    wnds.main_wnd(&mut wmngr).paste(text);
+                    wmngr.draw_top_invalidated();
+                }
+                &[]
+            }
+            InputResult::Resized { cols, rows } => {
+                rtwins::tr_info!("Terminal size: {}x{}", cols, rows);
+                // the UI must fit in the compositor screen model
+                #[cfg(feature = "compositor")]
+                let (cols, rows) = pal_compositor::fit_size(cols, rows);
+                wmngr.relayout(cols, rows);
+                #[cfg(feature = "compositor")]
+                pal_compositor::request_resize(cols, rows);
+                {
+                    let mut term_guard = TERM.try_lock().unwrap();
+                    term_guard.trace_row = trace_row(wnds.main_wnd(&mut wmngr), rows);
+                    term_guard.screen_clr_all();
+                }
+                wmngr.draw_all();
+                &[]
+            }
+            InputResult::Error(e) => {
+                rtwins::tr_err!("Input error: {:?}", e);
+                break;
+            }
+        };
 
-        if q {
-            rtwins::tr_warn!("Exit requested");
-            break;
-        }
-        else if !inp_seq.is_empty() {
+        if !inp_seq.is_empty() {
             ique.extend(inp_seq.iter());
 
             while dec.decode_input_seq(&mut ique, &mut ii) > 0 {
-                // check for Ctrl+D
+                // check for Ctrl+D, also when received with other keys
                 #[cfg(target_os = "none")]
-                if let InputEvent::Char(ref cb) = ii.evnt {
+                if let rtwins::input::InputEvent::Char(ref cb) = ii.evnt {
                     if cb.as_str() == "D" && ii.kmod.has_ctrl() {
                         rtwins::tr_warn!("Exit requested");
                         break 'mainloop;
@@ -287,10 +698,9 @@ fn tui() {
                 }
 
                 use rtwins::input::InputEvent;
-                use rtwins::input::Key;
 
                 // pass the input event to the top-window
-                let _key_handled = wmngr
+                let key_handled = wmngr
                     .get_top_mut()
                     .map_or(false, |ws| wgt::process_input(ws, &ii));
 
@@ -324,9 +734,11 @@ fn tui() {
                     InputEvent::None => {}
                 }
 
-                // input processing
-                if let InputEvent::Key(ref key) = ii.evnt {
-                    if *key == Key::F2 {
+                // global shortcuts, for the keys not taken by the window, like typed letters
+                let action = tetrary!(key_handled, None, keybindings.find(&ii));
+                match action {
+                    Some(Action::Help) => open_help(&mut wmngr, &keybindings),
+                    Some(Action::ToggleWndEnabled) => {
                         if let Some(top_ws) = wmngr.get_top_mut() {
                             let en = !top_ws.is_enabled(&top_ws.get_widgets()[0]);
 
@@ -337,7 +749,12 @@ fn tui() {
                             top_ws.invalidate(wgt::WIDGET_ID_ALL);
                         }
                     }
-                    else if *key == Key::F4 {
+                    Some(Action::ToggleStats) => {
+                        let main = wnds.main_wnd(&mut wmngr);
+                        main.show_stats = !main.show_stats;
+                        main.invalidate(wgt::WIDGET_ID_ALL);
+                    }
+                    Some(Action::ToggleMouse) => {
                         mouse_on = !mouse_on;
                         rtwins::tr_info!("Mouse {}", if mouse_on { "ON" } else { "OFF" });
                         let mut term_guard = TERM.try_lock().unwrap();
@@ -348,72 +765,51 @@ fn tui() {
                         ));
                         term_guard.flush_buff();
                     }
-                    else if *key == Key::F5 {
+                    Some(Action::Redraw) => {
                         TERM.try_lock().unwrap().screen_clr_all();
                         // draw windows from bottom to top
                         wmngr.draw_all();
+                        #[cfg(feature = "compositor")]
+                        rtwins::tr_info!("{}", pal_compositor::stats_str());
                     }
-                    else if *key == Key::F6 {
+                    Some(Action::TraceClear) => {
                         let mut term_guard = TERM.try_lock().unwrap();
                         term_guard.trace_area_clear();
                     }
-                    else if ii.kmod.has_ctrl() && (*key == Key::PgUp || *key == Key::PgDown) {
-                        if wmngr.is_top(WndMngr::MAIN) {
+                    Some(Action::ToggleDrawDebug) => {
+                        let en = !draw_debug::is_enabled();
+                        draw_debug::set_enabled(en);
+                        rtwins::tr_info!("Draw debug {}", if en { "ON" } else { "OFF" });
+                    }
+                    Some(Action::EditHits) => {
+                        if wmngr.is_top(wnds.main) {
+                            wnds.main_wnd(&mut wmngr).edit_hits();
+                        }
+                    }
+                    Some(action @ (Action::PagePrev | Action::PageNext)) => {
+                        if wmngr.is_top(wnds.main) {
                             if let Some(main_ws) = wmngr.get_top_mut() {
                                 wgt::pagectrl_select_next_page(
                                     main_ws,
                                     tui_main_def::id::PG_CONTROL,
-                                    *key == Key::PgDown,
+                                    action == Action::PageNext,
                                 );
                             }
                         }
                     }
-                    else if wmngr.is_top(WndMngr::MAIN) && (*key == Key::F9 || *key == Key::F10) {
-                        if let Some(main_ws) = wmngr.get_top_mut() {
-                            wgt::pagectrl_select_next_page(
-                                main_ws,
-                                tui_main_def::id::PG_CONTROL,
-                                *key == Key::F10,
-                            );
-                        }
-                    }
+                    None => {}
                 }
 
-                if wmngr.is_top(WndMngr::MAIN) {
-                    wmngr.main.rs.lbl.entry(id::LABEL_INPSEQ).or_default().txt =
+                if wmngr.is_top(wnds.main) {
+                    let main = wnds.main_wnd(&mut wmngr);
+                    main.rs.lbl.entry(id::LABEL_INPSEQ).or_default().txt =
                         rtwins::input_decoder::inp_seq_debug(inp_seq);
-                    wmngr.main.rs.lbl.entry(id::LABEL_INPNAME).or_default().txt =
-                        ii.name.to_owned();
-                    wmngr
-                        .main
-                        .invalidate_many(&[id::LABEL_INPNAME, id::LABEL_INPSEQ]);
+                    main.rs.lbl.entry(id::LABEL_INPNAME).or_default().txt = ii.name.to_owned();
+                    main.invalidate_many(&[id::LABEL_INPNAME, id::LABEL_INPSEQ]);
                 }
 
                 // process the command queue
-                {
-                    let cmdque = wmngr.cmdque.borrow_mut().take_commands();
-
-                    if let Some(cmdque) = cmdque {
-                        for cmd in cmdque.into_iter() {
-                            match cmd {
-                                Command::ShowPopup {
-                                    title,
-                                    message,
-                                    buttons,
-                                    on_button,
-                                } => {
-                                    rtwins::tr_info!("Command: ShowPopup");
-                                    wmngr.msgbox.setup(title, message, buttons, on_button);
-                                    wmngr.show(WndMngr::MSGBOX);
-                                }
-                                Command::HidePopup => {
-                                    rtwins::tr_info!("Command: HidePopup");
-                                    wmngr.hide(WndMngr::MSGBOX);
-                                }
-                            }
-                        }
-                    }
-                }
+                run_commands(&mut wmngr, &wnds);
 
                 wmngr.draw_top_invalidated();
 
@@ -425,14 +821,40 @@ fn tui() {
             } // decode_input_seq
         }
 
+        run_timers(&mut wmngr, &wnds, timestamp_ms());
+        expire_toasts(&mut wmngr, &wnds, timestamp_ms());
+        pal_stats::frame_end();
+
+        // the status line is refreshed after the frame, to not measure itself
+        if wnds.main_wnd(&mut wmngr).show_stats && wmngr.is_top(wnds.main) {
+            wnds.main_wnd(&mut wmngr).invalidate(id::LABEL_STATS);
+            wmngr.draw_top_invalidated();
+        }
+
         // flush the trace logs on every loop
         rtwins::tr_flush!(&mut TERM.try_lock().unwrap());
     }
 
     // epilogue
+    if let Some(ref st) = state_storage {
+        let saved = wnds.main_wnd(&mut wmngr).save_state().and_then(|blob| {
+            st.save(&blob)?;
+            Ok(blob.len())
+        });
+
+        match saved {
+            Ok(len) => rtwins::tr_info!("State saved, {} B", len),
+            Err(e) => rtwins::tr_err!("Cannot save the state: {:?}", e),
+        }
+    }
+
     {
+        #[cfg(feature = "compositor")]
+        rtwins::tr_info!("{}", pal_compositor::stats_str());
+
         let mut term_guard = TERM.try_lock().unwrap();
         term_guard.mouse_mode(rtwins::MouseMode::Off);
+        term_guard.write_str(input_source::PASTE_MODE_OFF);
         rtwins::tr_flush!(&mut term_guard);
 
         term_guard.pal.as_mut().sleep(1_000);
@@ -486,3 +908,1010 @@ fn test_property_access() {
     println!("sizeof Widget: {}", core::mem::size_of::<wgt::Widget>());
     println!("sizeof Property: {}", core::mem::size_of::<wgt::Property>());
 }
+
+/// Serializes the tests using the global TERM
+#[cfg(test)]
+static TERM_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Draws the windows prepared by `setup`, into the virtual screen
+#[cfg(test)]
+fn render_windows<F: FnOnce(&mut WndMngr, AppWnds)>(setup: F) -> vscreen::VScreen {
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let (pal, screen) = pal_vscreen::VScreenPal::new(100, 30);
+    TERM.try_lock().unwrap().pal = Box::new(pal);
+    tui_colors::init();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    setup(&mut wmngr, wnds);
+
+    screen.lock().unwrap().clear();
+    wmngr.draw_all();
+    TERM.try_lock().unwrap().flush_buff();
+
+    let scr = screen.lock().unwrap().clone();
+    scr
+}
+
+/// Draws the main window with given page selected, into the virtual screen
+#[cfg(test)]
+fn render_main_page(page_id: WId) -> vscreen::VScreen {
+    render_windows(|wmngr, wnds| {
+        wgt::pagectrl_select_page(wnds.main_wnd(wmngr), id::PG_CONTROL, page_id);
+    })
+}
+
+/// Compares the screen with `golden/<name>.txt` and `golden/<name>.ans` files;
+/// `UPDATE_GOLDEN=1` writes the files instead, a missing file fails the test
+#[cfg(test)]
+fn assert_golden(name: &str, scr: &vscreen::VScreen) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/tui_full/golden");
+    let update = std::env::var("UPDATE_GOLDEN").map_or(false, |v| v == "1");
+
+    for (ext, actual) in [("txt", scr.text()), ("ans", scr.to_ansi())] {
+        let path = dir.join(format!("{}.{}", name, ext));
+
+        match std::fs::read_to_string(&path) {
+            Ok(ref expected) if !update => {
+                if *expected != actual {
+                    panic!(
+                        "{} differs from the golden file (run with UPDATE_GOLDEN=1 to accept):\n{}",
+                        path.display(),
+                        golden_diff(expected, &actual)
+                    );
+                }
+            }
+            Err(e) if !update => {
+                panic!("{}: {} (run with UPDATE_GOLDEN=1 to create it)", path.display(), e);
+            }
+            _ => {
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(&path, &actual).unwrap();
+                println!("Golden file written: {}", path.display());
+            }
+        }
+    }
+}
+
+/// Line-by-line diff; escape codes are made visible
+#[cfg(test)]
+fn golden_diff(expected: &str, actual: &str) -> String {
+    let exp_lines: Vec<&str> = expected.lines().collect();
+    let act_lines: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+
+    for nr in 0..exp_lines.len().max(act_lines.len()) {
+        let exp = exp_lines.get(nr).copied().unwrap_or("");
+        let act = act_lines.get(nr).copied().unwrap_or("");
+
+        if exp != act {
+            out.push_str(&format!("{:3}- {}\n", nr + 1, exp.replace('\x1b', "␛")));
+            out.push_str(&format!("{:3}+ {}\n", nr + 1, act.replace('\x1b', "␛")));
+        }
+    }
+
+    out
+}
+
+#[test]
+fn test_vscreen_parser() {
+    use vscreen::{attr, Color};
+
+    let mut scr = vscreen::VScreen::new(20, 5);
+    scr.write_str("\x1b[2;3H\x1b[1;31mAB\x1b[0m\x1b[38;5;200;48;2;1;2;3mC🍋D\x1b[?1000h");
+
+    assert_eq!(scr.row_text(1), "  ABC🍋D");
+    assert_eq!(scr.cursor(), (8, 1));
+    assert!(scr.is_mode_set(1000));
+
+    let a = scr.cell(2, 1).unwrap();
+    assert_eq!(a.ch, 'A');
+    assert_eq!(a.fg, Color::Idx(1));
+    assert_eq!(a.attr, attr::BOLD);
+
+    let c = scr.cell(4, 1).unwrap();
+    assert_eq!(c.fg, Color::Idx(200));
+    assert_eq!(c.bg, Color::Rgb(1, 2, 3));
+    assert_eq!(c.attr, 0);
+
+    assert_eq!(scr.find_text("D"), Some((7, 1)));
+
+    scr.write_str("\x1b[2;4H\x1b[K");
+    assert_eq!(scr.row_text(1), "  A");
+
+    // cursor moves are clamped to the screen
+    scr.write_str("\x1b[65535B\x1b[65535C");
+    assert_eq!(scr.cursor(), (19, 4));
+
+    let mut empty = vscreen::VScreen::new(0, 0);
+    empty.write_str("\x1b[65535B\x1b[5C\tX\n");
+    assert_eq!(empty.cursor(), (0, 0));
+}
+
+#[test]
+fn test_render_main_pages() {
+    let pages = [
+        (id::PAGE_PANELS, "KEY-CODES"),
+        (id::PAGE_LISTBOX, "ListBox manual:"),
+        (id::PAGE_TEXTEDIT, "Check list:"),
+        (id::PAGE_COMBOBOX, "Say YES"),
+        (id::PAGE_TEXTBOX, "Lorem ipsum"),
+        (id::PAGE_INACTIV, "Custom Widget:"),
+        (id::PAGE_MISC, "Word-wrap"),
+    ];
+
+    for (page_id, txt) in pages.iter() {
+        let scr = render_main_page(*page_id);
+
+        assert!(scr.find_text("Service Menu").is_some());
+        assert!(
+            scr.find_text(txt).is_some(),
+            "page {}: '{}' not found in:\n{}",
+            page_id,
+            txt,
+            scr.text()
+        );
+
+        // content of other pages is hidden
+        for (other_id, other_txt) in pages.iter().filter(|(id, _)| id != page_id) {
+            assert!(
+                scr.find_text(other_txt).is_none(),
+                "page {}: '{}' of page {} is visible",
+                page_id,
+                other_txt,
+                other_id
+            );
+        }
+    }
+}
+
+/// Page widget ids with the golden files name suffix
+#[cfg(test)]
+const MAIN_PAGES: [(WId, &str); 7] = [
+    (id::PAGE_PANELS, "panels"),
+    (id::PAGE_LISTBOX, "listbox"),
+    (id::PAGE_TEXTEDIT, "textedit"),
+    (id::PAGE_COMBOBOX, "combobox"),
+    (id::PAGE_TEXTBOX, "textbox"),
+    (id::PAGE_INACTIV, "inactiv"),
+    (id::PAGE_MISC, "misc"),
+];
+
+#[test]
+fn test_golden_main_pages() {
+    for (page_id, name) in MAIN_PAGES.iter() {
+        let scr = render_main_page(*page_id);
+        assert_golden(&format!("main_{}", name), &scr);
+    }
+}
+
+#[test]
+fn test_golden_main_pages_disabled() {
+    for (page_id, name) in MAIN_PAGES.iter() {
+        // the same as F2 does
+        let scr = render_windows(|wmngr, wnds| {
+            wgt::pagectrl_select_page(wnds.main_wnd(wmngr), id::PG_CONTROL, *page_id);
+            wnds.main_wnd(wmngr).rs.set_enabled(id::WND_MAIN, false);
+        });
+        assert_golden(&format!("main_{}_disabled", name), &scr);
+    }
+}
+
+#[test]
+fn test_golden_msgbox_buttons() {
+    let all_buttons = "ynoc";
+
+    // every non-empty subset of the buttons
+    for mask in 1..(1u8 << all_buttons.len()) {
+        let buttons: String = all_buttons
+            .chars()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, ch)| ch)
+            .collect();
+        let buttons: &'static str = Box::leak(buttons.into_boxed_str());
+
+        let scr = render_windows(|wmngr, _| {
+            open_msgbox(
+                wmngr,
+                "Golden".to_owned(),
+                "Lorem ipsum dolor sit amet, consectetur adipiscing elit.".to_owned(),
+                buttons,
+                None,
+                0,
+            );
+        });
+
+        assert!(scr.find_text("Golden").is_some());
+        assert_golden(&format!("msgbox_{}", buttons), &scr);
+    }
+}
+
+#[test]
+fn test_golden_msgbox_disabled() {
+    let scr = render_windows(|wmngr, _| {
+        let wnd_id = open_msgbox(
+            wmngr,
+            "Golden".to_owned(),
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit.".to_owned(),
+            "ynoc",
+            None,
+            0,
+        );
+        let msgbox = wmngr.get_as_mut::<tui_msgbox_state::MsgBoxState>(wnd_id).unwrap();
+        msgbox.rs.set_enabled(tui_msgbox_def::idmb::WND_MSGBOX, false);
+    });
+
+    assert_golden("msgbox_ynoc_disabled", &scr);
+}
+
+#[test]
+fn test_relayout_small_screen() {
+    let scr = render_windows(|wmngr, _| {
+        wmngr.relayout(40, 12);
+        open_msgbox(
+            wmngr,
+            "Resized".to_owned(),
+            "Popup must stay on the screen".to_owned(),
+            "o",
+            None,
+            0,
+        );
+    });
+
+    let (col, row) = scr.find_text("Resized").expect("popup title not found");
+    assert!(col < 40 && row < 12, "popup title at {}:{}", col, row);
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    let main = wnds.main_wnd(&mut wmngr);
+    assert_eq!(trace_row(main, u16::MAX), 18);
+    assert_eq!(trace_row(main, 12), 12 - TRACE_ROWS_MIN);
+}
+
+/// Returns the prepared chunks, one per call; the empty chunk is a 100 ms pause,
+/// returned as the timeout if the read timeout is shorter
+#[cfg(test)]
+struct ScriptedInput {
+    chunks: Vec<&'static [u8]>,
+}
+
+#[cfg(test)]
+impl InputSource for ScriptedInput {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        if self.chunks.first().map_or(false, |c| c.is_empty()) {
+            self.chunks.remove(0);
+
+            if timeout_ms < 100 {
+                return InputResult::Timeout;
+            }
+        }
+
+        if self.chunks.is_empty() {
+            InputResult::Quit
+        }
+        else {
+            InputResult::Data(self.chunks.remove(0))
+        }
+    }
+}
+
+#[test]
+fn test_input_paste() {
+    let src = ScriptedInput {
+        chunks: vec![
+            b"a\x1b[200~Hello",
+            b", pasted\nworld\x1b[20",
+            b"1~b",
+            b"\x1b[200~",
+            b"tail",
+        ],
+    };
+    let mut inp = input_paste::InputPaste::new(Box::new(src));
+
+    assert!(matches!(inp.read_input(0), InputResult::Data(b"a")));
+    assert!(matches!(inp.read_input(0), InputResult::Paste("Hello, pasted\nworld")));
+    assert!(matches!(inp.read_input(0), InputResult::Data(b"b")));
+    // source closed before the end marker
+    assert!(matches!(inp.read_input(0), InputResult::Paste("tail")));
+    assert!(matches!(inp.read_input(0), InputResult::Quit));
+
+    // begin marker split between the reads
+    let src = ScriptedInput {
+        chunks: vec![b"x\x1b[2", b"00~text\x1b[201~", b"\x1b"],
+    };
+    let mut inp = input_paste::InputPaste::new(Box::new(src));
+
+    assert!(matches!(inp.read_input(0), InputResult::Data(b"x")));
+    assert!(matches!(inp.read_input(0), InputResult::Paste("text")));
+    // lone Esc
+    assert!(matches!(inp.read_input(0), InputResult::Data(b"\x1b")));
+    assert!(matches!(inp.read_input(0), InputResult::Quit));
+
+    // the paste is not cut by the short timeouts
+    let src = ScriptedInput {
+        chunks: vec![b"\x1b[200~abc", b"", b"def\x1b[20", b"", b"1~", b"", b"g"],
+    };
+    let mut inp = input_paste::InputPaste::new(Box::new(src));
+
+    assert!(matches!(inp.read_input(0), InputResult::Paste("abcdef")));
+    assert!(matches!(inp.read_input(0), InputResult::Timeout));
+    assert!(matches!(inp.read_input(0), InputResult::Data(b"g")));
+}
+
+#[test]
+fn test_log_strip_ansi() {
+    use log_file::strip_ansi;
+
+    assert_eq!(strip_ansi("\x1b[1mBold\x1b[0m text\r\n"), "Bold text\n");
+    assert_eq!(strip_ansi("\x1b]8;;http://x\x1b\\link\x1b]8;;\x07"), "link");
+    assert_eq!(strip_ansi("one\x1b[20;1Htwo\x1b7"), "one\ntwo");
+    assert_eq!(strip_ansi("\x1b[38;2;1;2;3m🔶 ok"), "🔶 ok");
+}
+
+#[test]
+fn test_log_pal_over_compositor() {
+    use rtwins::pal::Pal;
+
+    let path = std::env::temp_dir().join("rtwins_test_log.txt");
+    let _ = std::fs::remove_file(&path);
+    let log_file = log_file::LogFile::open(&path.to_string_lossy()).unwrap();
+
+    // the compositor drops what did not change on the screen, the log gets all the traces
+    let (inner, _screen) = pal_vscreen::VScreenPal::new(40, 10);
+    let pal = pal_compositor::CompositorPal::new(Box::new(inner), 40, 10);
+    let mut pal = log_file::LogPal::new(Box::new(pal), log_file);
+
+    for _ in 0..2 {
+        pal.mark_logging(true);
+        pal.write_str_n("\x1b[5;1Hsame trace", 1);
+        pal.mark_logging(false);
+        pal.flush_buff();
+    }
+    pal.write_str_n("not a trace", 1);
+    pal.flush_buff();
+    drop(pal);
+
+    let logged = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(logged, "same trace\nsame trace\n");
+}
+
+#[test]
+fn test_tcp_loopback() {
+    use rtwins::pal::Pal;
+    use std::io::{Read, Write};
+
+    let server = pal_tcp::TcpServer::bind(0).unwrap();
+    let port = server.local_port();
+
+    let client = std::thread::spawn(move || {
+        let mut sock = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        // IAC SB NAWS 0 100 0 30 IAC SE, then "a", IAC IAC, Enter
+        sock.write_all(&[255, 250, 31, 0, 100, 0, 30, 255, 240]).unwrap();
+        sock.write_all(b"a\xff\xff\r\0").unwrap();
+
+        let mut rcvd = Vec::new();
+        let mut buff = [0u8; 64];
+        while !rcvd.ends_with(b"Hello") {
+            let nb = sock.read(&mut buff).unwrap();
+            assert!(nb > 0, "server closed the connection");
+            rcvd.extend_from_slice(&buff[..nb]);
+        }
+        // the negotiation comes first
+        assert_eq!(rcvd[0], 255);
+        // disconnect
+    });
+
+    let (mut pal, mut inp) = server.accept().unwrap();
+
+    let mut rcvd = Vec::new();
+    let mut resized = None;
+    while rcvd.len() < 3 {
+        match inp.read_input(100) {
+            InputResult::Data(seq) => rcvd.extend_from_slice(seq),
+            InputResult::Resized { cols, rows } => resized = Some((cols, rows)),
+            InputResult::Timeout => {}
+            _ => panic!("unexpected input result"),
+        }
+    }
+    assert_eq!(resized, Some((100, 30)));
+    assert_eq!(rcvd, b"a\xff\r");
+
+    pal.write_str_n("Hello", 1);
+    pal.flush_buff();
+    client.join().unwrap();
+
+    let quit = loop {
+        match inp.read_input(100) {
+            InputResult::Timeout => continue,
+            res => break matches!(res, InputResult::Quit),
+        }
+    };
+    assert!(quit, "disconnection not reported");
+}
+
+#[test]
+fn test_compositor_output() {
+    use core::sync::atomic::Ordering;
+
+    let expected = render_windows(|_, _| {});
+
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (inner, screen) = pal_vscreen::VScreenPal::new(100, 30);
+    let pal = pal_compositor::CompositorPal::new(Box::new(inner), 100, 30);
+    TERM.try_lock().unwrap().pal = Box::new(pal);
+    tui_colors::init();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    wmngr.draw_all();
+    TERM.try_lock().unwrap().flush_buff();
+
+    let scr = screen.lock().unwrap().clone();
+    assert_eq!(scr.text(), expected.text());
+    assert_eq!(scr.to_ansi(), expected.to_ansi());
+
+    // nothing changed - almost nothing is sent
+    let bytes_in = pal_compositor::BYTES_IN.load(Ordering::Relaxed);
+    let bytes_out = pal_compositor::BYTES_OUT.load(Ordering::Relaxed);
+    wmngr.draw_all();
+    TERM.try_lock().unwrap().flush_buff();
+    let drawn = pal_compositor::BYTES_IN.load(Ordering::Relaxed) - bytes_in;
+    let sent = pal_compositor::BYTES_OUT.load(Ordering::Relaxed) - bytes_out;
+
+    assert!(drawn > 1000, "drawn {} B", drawn);
+    assert!(sent < 100, "sent {} B", sent);
+    assert_eq!(screen.lock().unwrap().text(), expected.text());
+
+    // only the size query is passed, not the text around it
+    let mut out = String::new();
+    pal_compositor::passthrough_seqs("ab\x1b[?1000h\x1b7\x1b[999;999H\x1b[6n\x1b8cd", &mut out);
+    assert_eq!(out, "\x1b[?1000h\x1b7\x1b[999;999H\x1b[6n\x1b8");
+}
+
+#[test]
+fn test_stats_status_line() {
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (inner, screen) = pal_vscreen::VScreenPal::new(100, 30);
+    TERM.try_lock().unwrap().pal = Box::new(pal_stats::StatsPal::new(Box::new(inner)));
+    tui_colors::init();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+
+    pal_stats::frame_begin();
+    wmngr.draw_all();
+    TERM.try_lock().unwrap().flush_buff();
+    pal_stats::frame_end();
+
+    let summary = pal_stats::summary();
+    assert!(!summary.contains("Last frame: 0 B"), "{}", summary);
+    assert!(screen.lock().unwrap().text().contains("F2 Wnd En"));
+
+    // the status line replaces the footer
+    wnds.main_wnd(&mut wmngr).show_stats = true;
+    wnds.main_wnd(&mut wmngr).invalidate(wgt::WIDGET_ID_ALL);
+    wmngr.draw_top_invalidated();
+    TERM.try_lock().unwrap().flush_buff();
+
+    let text = screen.lock().unwrap().text();
+    assert!(text.contains("Last frame:"), "{}", text);
+    assert!(!text.contains("F2 Wnd En"), "{}", text);
+}
+
+/// Keeps the output of every flush, passing it to the wrapped PAL
+#[cfg(test)]
+struct CapturePal {
+    inner: Box<dyn rtwins::pal::Pal>,
+    buff: String,
+    flushes: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl rtwins::pal::Pal for CapturePal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.buff.push(c);
+        }
+        self.inner.write_char_n(c, repeat);
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        for _ in 0..repeat {
+            self.buff.push_str(s);
+        }
+        self.inner.write_str_n(s, repeat);
+    }
+
+    fn flush_buff(&mut self) {
+        let out = core::mem::take(&mut self.buff);
+        self.flushes.lock().unwrap().push(out);
+        self.inner.flush_buff();
+    }
+
+    fn sleep(&self, ms: u16) {
+        self.inner.sleep(ms);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        self.inner.get_timestamp_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.inner.get_timespan_ms(prev_timestamp)
+    }
+}
+
+#[test]
+fn test_draw_debug_flash() {
+    let expected = render_windows(|_, _| {});
+
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (inner, screen) = pal_vscreen::VScreenPal::new(100, 30);
+    let flushes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let pal = CapturePal {
+        inner: Box::new(inner),
+        buff: String::new(),
+        flushes: std::sync::Arc::clone(&flushes),
+    };
+    TERM.try_lock().unwrap().pal = Box::new(pal);
+    tui_colors::init();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    wmngr.draw_all();
+    TERM.try_lock().unwrap().flush_buff();
+    assert!(flushes.lock().unwrap().iter().all(|out| !out.contains("\x1b[43m")));
+
+    // highlighted, then repainted
+    flushes.lock().unwrap().clear();
+    draw_debug::set_enabled(true);
+    wnds.main_wnd(&mut wmngr).invalidate(id::PANEL_VERSIONS);
+    wmngr.draw_top_invalidated();
+    draw_debug::set_enabled(false);
+    TERM.try_lock().unwrap().flush_buff();
+
+    let size = {
+        let main = wnds.main_wnd(&mut wmngr);
+        wgt::find_by_id(main.get_widgets(), id::PANEL_VERSIONS).unwrap().size
+    };
+    let outs = flushes.lock().unwrap().clone();
+    let flash = &outs[0];
+    assert!(flash.starts_with("\x1b[43m") && flash.ends_with("\x1b[0m"), "{:?}", flash);
+    let blank = " ".repeat(size.width as usize);
+    assert_eq!(flash.matches(&blank).count(), size.height as usize, "{:?}", flash);
+    assert!(outs[1..].iter().all(|out| !out.contains("\x1b[43m")));
+
+    let mut wids = vec![];
+    wnds.main_wnd(&mut wmngr).get_invalidated(&mut wids);
+    assert!(wids.is_empty());
+    assert_eq!(screen.lock().unwrap().text(), expected.text());
+
+    // the whole window drawn is highlighted as well
+    flushes.lock().unwrap().clear();
+    draw_debug::set_enabled(true);
+    wmngr.draw_all();
+    draw_debug::set_enabled(false);
+    TERM.try_lock().unwrap().flush_buff();
+
+    let outs = flushes.lock().unwrap().clone();
+    assert!(outs[0].starts_with("\x1b[43m"), "{:?}", outs[0]);
+    assert_eq!(screen.lock().unwrap().text(), expected.text());
+}
+
+#[test]
+fn test_wnd_mngr_registry() {
+    // the windows are drawn through the global TERM
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+
+    let popup = open_msgbox(&mut wmngr, "A".to_owned(), "".to_owned(), "o", Some(wnds.main), 0);
+    assert_eq!((wnds.main, wnds.toast, popup), (0, 1, 2));
+    assert!(wmngr.get_as::<tui_main_state::MainWndState>(wnds.main).is_some());
+    assert!(wmngr.get_as::<tui_main_state::MainWndState>(popup).is_none());
+
+    let order: Vec<WId> = wmngr.iter().map(|(wid, _)| wid).collect();
+    assert_eq!(order, [wnds.main, popup]);
+
+    // removed window is hidden, and its id is reused
+    assert!(wmngr.remove(popup).is_some());
+    assert!(wmngr.get_wnd(popup).is_none());
+    assert!(wmngr.is_top(wnds.main));
+
+    let popup2 = open_msgbox(&mut wmngr, "B".to_owned(), "".to_owned(), "o", Some(wnds.main), 0);
+    assert_eq!(popup2, popup);
+    assert_eq!(wmngr.get_as::<tui_msgbox_state::MsgBoxState>(popup2).unwrap().wnd_id, popup2);
+}
+
+#[test]
+fn test_nested_msgbox() {
+    use rtwins::input::InputInfo;
+    use tui_msgbox_def::idmb;
+
+    let scr = render_windows(|wmngr, wnds| {
+        let outer = open_msgbox(
+            wmngr,
+            "Outer".to_owned(),
+            "Main window asks for the confirmation of NO".to_owned(),
+            "yn",
+            Some(wnds.main),
+            tui_main_state::DLG_LOREM,
+        );
+        // stacked over the outer one
+        let other = open_msgbox(wmngr, "Other".to_owned(), "".to_owned(), "o", Some(wnds.main), 0);
+        assert!(wmngr.is_top(other));
+        wmngr.remove(other);
+
+        let btn_no = wgt::find_by_id(&tui_msgbox_def::WND_MSGBOX_WGTS[..], idmb::BTN_NO).unwrap();
+        let msgbox = wmngr.get_as_mut::<tui_msgbox_state::MsgBoxState>(outer).unwrap();
+        msgbox.on_button_click(btn_no, &InputInfo::default());
+        run_commands(wmngr, &wnds);
+
+        // the outer popup is closed, the main window opened the inner one
+        assert_eq!(wmngr.get_visible().len(), 2);
+        assert!(!wmngr.is_top(wnds.main));
+        let top_ws = wmngr.get_top_mut().unwrap();
+        let mut title = String::new();
+        top_ws.get_window_title(&tui_msgbox_def::WND_MSGBOX_WGTS[0], &mut title);
+        assert_eq!(title, "Confirm");
+    });
+
+    assert!(scr.find_text("Confirm").is_some());
+    assert!(scr.find_text("Outer").is_none());
+}
+
+#[test]
+fn test_input_dialog() {
+    use rtwins::input::InputInfo;
+    use tui_inputdlg_def::idinp;
+
+    let scr = render_windows(|wmngr, wnds| {
+        wgt::pagectrl_select_page(wnds.main_wnd(wmngr), id::PG_CONTROL, id::PAGE_INACTIV);
+
+        // the button does nothing
+        let btn_noaction = wgt::find_by_id(&tui_main_def::WND_MAIN_WGTS[..], id::BTN_NOACTION);
+        wnds.main_wnd(wmngr)
+            .on_button_click(btn_noaction.unwrap(), &InputInfo::default());
+        run_commands(wmngr, &wnds);
+        assert!(wmngr.is_top(wnds.main));
+
+        let kb = KeyBindings::new();
+        let trigger = tui_keybindings::Trigger::Key(rtwins::input::Key::F11);
+        assert_eq!(kb.find_trigger(trigger, false), Some(Action::EditHits));
+        wnds.main_wnd(wmngr).edit_hits();
+        run_commands(wmngr, &wnds);
+        let dlg_id = *wmngr.get_visible().last().unwrap();
+        let btn_ok = wgt::find_by_id(&tui_inputdlg_def::WND_INPUTDLG_WGTS[..], idinp::BTN_OK);
+
+        // out of range - the dialog stays
+        let dlg = wmngr.get_as_mut::<tui_inputdlg_state::InputDlgState>(dlg_id).unwrap();
+        dlg.rs.txte.entry(idinp::EDIT_VALUE).or_default().txt = "150".to_owned();
+        dlg.on_button_click(btn_ok.unwrap(), &InputInfo::default());
+        run_commands(wmngr, &wnds);
+        assert!(wmngr.is_top(dlg_id));
+
+        let dlg = wmngr.get_as_mut::<tui_inputdlg_state::InputDlgState>(dlg_id).unwrap();
+        dlg.rs.txte.entry(idinp::EDIT_VALUE).or_default().txt = "42".to_owned();
+        dlg.on_button_click(btn_ok.unwrap(), &InputInfo::default());
+        run_commands(wmngr, &wnds);
+        assert!(wmngr.is_top(wnds.main));
+    });
+
+    assert!(scr.find_text("42 Hits").is_some());
+}
+
+#[test]
+fn test_toasts() {
+    use rtwins::input::InputInfo;
+    use tui_toast_state::TOAST_DURATION_MS;
+
+    let expected = render_windows(|_, _| {});
+
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (inner, screen) = pal_vscreen::VScreenPal::new(100, 30);
+    let flushes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let pal = CapturePal {
+        inner: Box::new(inner),
+        buff: String::new(),
+        flushes: std::sync::Arc::clone(&flushes),
+    };
+    TERM.try_lock().unwrap().pal = Box::new(pal);
+    tui_colors::init();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    wmngr.draw_all();
+
+    let btn_toaster = wgt::find_by_id(&tui_main_def::WND_MAIN_WGTS[..], id::BTN_TOASTER).unwrap();
+    wnds.main_wnd(&mut wmngr).on_button_click(btn_toaster, &InputInfo::default());
+    run_commands(&mut wmngr, &wnds);
+    let shown_at = timestamp_ms();
+    wnds.main_wnd(&mut wmngr).on_button_click(btn_toaster, &InputInfo::default());
+    run_commands(&mut wmngr, &wnds);
+    TERM.try_lock().unwrap().flush_buff();
+
+    let text = screen.lock().unwrap().text();
+    assert!(text.contains("Toast #1: Info"), "{}", text);
+    assert!(text.contains("Toast #2: Warning"), "{}", text);
+    // the toasts get no input
+    assert!(wmngr.is_top(wnds.main));
+
+    // nothing changed below - the overlay is not redrawn
+    flushes.lock().unwrap().clear();
+    wmngr.draw_top_invalidated();
+    TERM.try_lock().unwrap().flush_buff();
+    let out = flushes.lock().unwrap().concat();
+    assert!(!out.contains("Toast #1"), "{:?}", out);
+
+    expire_toasts(&mut wmngr, &wnds, shown_at + TOAST_DURATION_MS / 2);
+    assert!(wmngr.is_overlay_visible(wnds.toast));
+
+    // the area below is restored
+    expire_toasts(&mut wmngr, &wnds, shown_at + TOAST_DURATION_MS * 2);
+    TERM.try_lock().unwrap().flush_buff();
+    assert!(!wmngr.is_overlay_visible(wnds.toast));
+    assert_eq!(screen.lock().unwrap().text(), expected.text());
+}
+
+#[test]
+fn test_keybindings() {
+    use rtwins::input::Key;
+    use tui_keybindings::Trigger;
+
+    let mut kb = KeyBindings::new();
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F5), false), Some(Action::Redraw));
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F5), true), None);
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::PgDown), true), Some(Action::PageNext));
+    assert_eq!(kb.find_trigger(Trigger::Char('l'), true), Some(Action::Redraw));
+    assert_eq!(kb.find_trigger(Trigger::Char('l'), false), None);
+
+    let conf = "# custom keys\n redraw = F8, C-R \n\npage_next = F12 # no Ctrl-N\n";
+    assert_eq!(kb.load(conf), Ok(()));
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F5), false), None);
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F8), false), Some(Action::Redraw));
+    assert_eq!(kb.find_trigger(Trigger::Char('r'), true), Some(Action::Redraw));
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F12), false), Some(Action::PageNext));
+    assert_eq!(kb.find_trigger(Trigger::Char('n'), true), None);
+    // other actions keep the defaults
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F9), false), Some(Action::PagePrev));
+
+    // errors leave the table unchanged
+    assert!(kb.load("mouse = F4\nredraw = C-F99").unwrap_err().contains("line 2"));
+    assert!(kb.load("bogus = F1").unwrap_err().contains("unknown action"));
+    assert!(kb.load("redraw F5").is_err());
+    assert_eq!(kb.find_trigger(Trigger::Key(Key::F8), false), Some(Action::Redraw));
+}
+
+#[test]
+fn test_help_window() {
+    use rtwins::input::{InputEvent, InputInfo, Key};
+
+    let mut keybindings = KeyBindings::new();
+    keybindings.load("redraw = F8").unwrap();
+
+    let scr = render_windows(|wmngr, _| {
+        open_help(wmngr, &keybindings);
+        // not stacked twice
+        open_help(wmngr, &keybindings);
+        assert_eq!(wmngr.get_visible().len(), 2);
+
+        let help_id = *wmngr.get_visible().last().unwrap();
+        let help = wmngr.get_as::<tui_help_state::HelpState>(help_id).unwrap();
+        let lines = help.get_lines();
+        let lines = lines.borrow();
+        // longer than the text box, so it scrolls
+        assert!(lines.len() > tui_help_def::WND_HELP_WGTS[1].size.height as usize - 2);
+        assert!(lines.iter().any(|l| l.contains("F8") && l.contains("Redraw")));
+        assert!(lines.iter().any(|l| l.contains("PgUp/PgDown")));
+    });
+
+    assert!(scr.find_text("Global shortcuts").is_some());
+    assert!(scr.find_text("F1, Ctrl+U").is_some());
+
+    // Esc closes the help
+    let scr = render_windows(|wmngr, wnds| {
+        open_help(wmngr, &keybindings);
+        let mut ii = InputInfo::default();
+        ii.evnt = InputEvent::Key(Key::Esc);
+        let top_ws = wmngr.get_top_mut().unwrap();
+        wgt::process_input(top_ws, &ii);
+        run_commands(wmngr, &wnds);
+        assert!(wmngr.is_top(wnds.main));
+    });
+
+    assert!(scr.find_text("Global shortcuts").is_none());
+}
+
+#[test]
+fn test_main_paste() {
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    let main = wnds.main_wnd(&mut wmngr);
+
+    // not edited: appended to the text, with no key events
+    main.set_focused_id(id::EDIT1);
+    let txt = main.rs.txte.entry(id::EDIT1).or_default().txt.clone();
+    main.paste("a\tb");
+    assert_eq!(main.rs.txte[&id::EDIT1].txt, txt + "a b");
+
+    // only the text edits take the paste
+    main.set_focused_id(id::EDIT2);
+    main.paste("x");
+    assert!(!main.rs.txte.get(&id::EDIT2).map_or(false, |rs| rs.txt.contains('x')));
+}
+
+#[test]
+fn test_persist_state() {
+    use rtwins::wgt::rstate::*;
+
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    let main = wnds.main_wnd(&mut wmngr);
+
+    main.rs.chbx.insert(id::CHBX_L1, ChbxState { checked: false });
+    main.rs.txte.entry(id::EDIT1).or_default().txt = "Zażółć 🍋".to_owned();
+    main.rs.lbx.entry(id::LIST_BOX).or_default().sel_idx = 7;
+    main.rs.pgctrl.entry(id::PG_CONTROL).or_default().page = 2;
+    main.set_focused_id(id::EDIT2);
+    let blob = main.save_state().unwrap();
+
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    let main = wnds.main_wnd(&mut wmngr);
+    assert_eq!(main.restore_state(&blob), Ok(()));
+
+    assert!(!main.rs.chbx[&id::CHBX_L1].checked);
+    assert_eq!(main.rs.txte[&id::EDIT1].txt, "Zażółć 🍋");
+    assert_eq!(main.rs.txte[&id::EDIT_PSW].txt, "pssst!");
+    assert_eq!(main.rs.lbx[&id::LIST_BOX].sel_idx, 7);
+    assert_eq!(main.rs.pgctrl[&id::PG_CONTROL].page, 2);
+    assert_eq!(main.get_focused_id(), id::EDIT2);
+
+    // unknown section, eg. from the newer app version, is skipped
+    let fingerprint = tui_persist::fingerprint(&tui_main_def::WND_MAIN_WGTS);
+    let mut w = tui_persist::BlobWriter::new(fingerprint);
+    w.section(0xFE, |w| w.put_str("future"));
+    tui_persist::write_rstate(&mut w, &main.rs);
+    assert_eq!(main.restore_state(&w.finish().unwrap()), Ok(()));
+
+    // damaged or foreign blobs are rejected
+    use tui_persist::PersistError;
+    let mut damaged = blob.clone();
+    damaged[10] ^= 0x55;
+    assert_eq!(main.restore_state(&damaged), Err(PersistError::Checksum));
+    assert_eq!(main.restore_state(&blob[..5]), Err(PersistError::Truncated));
+    assert_eq!(main.restore_state(b"garbage"), Err(PersistError::BadMagic));
+
+    let foreign = tui_persist::BlobWriter::new(0x1234).finish().unwrap();
+    assert_eq!(main.restore_state(&foreign), Err(PersistError::Fingerprint(0x1234)));
+
+    // the lengths must fit in the u16
+    let long = "x".repeat(70_000);
+    let mut w = tui_persist::BlobWriter::new(fingerprint);
+    w.section(0xFE, |w| w.put_str(&long));
+    assert_eq!(w.finish(), Err(PersistError::TooLarge));
+    let mut w = tui_persist::BlobWriter::new(fingerprint);
+    w.section(0xFE, |w| (0..70_000).for_each(|_| w.put_u8(0)));
+    assert_eq!(w.finish(), Err(PersistError::TooLarge));
+
+    // the file storage reports the failed write
+    let path = std::env::temp_dir().join("rtwins_test_persist.bin");
+    let st = tui_persist::Storage::File(path.to_string_lossy().into_owned());
+    assert_eq!(st.save(&blob), Ok(()));
+    assert_eq!(st.load(), Some(blob.clone()));
+    let _ = std::fs::remove_file(&path);
+
+    let st = tui_persist::Storage::File("/nonexistent/dir/state.bin".to_owned());
+    assert_eq!(st.save(&blob), Err(PersistError::Write));
+}
+
+#[test]
+fn test_timer_service() {
+    use tui_timers::TimerService;
+
+    let mut timers = TimerService::default();
+    assert_eq!(timers.next_timeout_ms(0), None);
+
+    // close to the timestamp wrap-around
+    let t0 = u32::MAX - 100;
+    timers.start(1, 10, 300, true, t0);
+    timers.start(2, 20, 150, false, t0);
+    assert_eq!(timers.next_timeout_ms(t0), Some(150));
+    assert!(timers.take_expired(t0.wrapping_add(149)).is_empty());
+
+    assert_eq!(timers.take_expired(t0.wrapping_add(150)), [(2, 20)]);
+    assert_eq!(timers.next_timeout_ms(t0.wrapping_add(150)), Some(150));
+    assert_eq!(timers.take_expired(t0.wrapping_add(310)), [(1, 10)]);
+    // periodic one keeps the pace
+    assert_eq!(timers.next_timeout_ms(t0.wrapping_add(310)), Some(290));
+
+    // overdue
+    assert_eq!(timers.next_timeout_ms(t0.wrapping_add(700)), Some(0));
+    timers.stop(1, 10);
+    assert_eq!(timers.next_timeout_ms(t0), None);
+}
+
+#[test]
+fn test_main_wnd_timers() {
+    use rtwins::wgt::rstate::PgbarState;
+
+    // the timestamps come from the TERM
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    run_commands(&mut wmngr, &wnds);
+
+    let pos = |wmngr: &mut WndMngr| {
+        let main = wnds.main_wnd(wmngr);
+        let mut pgbar = PgbarState::default();
+        main.get_progress_bar_state(
+            wgt::find_by_id(&tui_main_def::WND_MAIN_WGTS[..], id::PRGBAR1).unwrap(),
+            &mut pgbar,
+        );
+        pgbar.pos
+    };
+
+    let pos0 = pos(&mut wmngr);
+    let now = timestamp_ms();
+    let wait_ms = wmngr.timers.next_timeout_ms(now).expect("animation timers not started");
+    assert!(wait_ms <= 500);
+
+    // both timers are due
+    let expired = wmngr.timers.take_expired(now.wrapping_add(500));
+    assert_eq!(expired.len(), 2);
+    for (owner, token) in expired.into_iter() {
+        wmngr.get_wnd_mut(owner).unwrap().on_timer(token);
+    }
+
+    assert_eq!(pos(&mut wmngr), pos0 + 1);
+    let led = wgt::find_by_id(&tui_main_def::WND_MAIN_WGTS[..], id::LED_PUMP).unwrap();
+    assert!(wnds.main_wnd(&mut wmngr).get_led_lit(led));
+
+    // the closed window timers are stopped
+    wmngr.remove(wnds.main);
+    assert_eq!(wmngr.timers.next_timeout_ms(now), None);
+}
+
+#[test]
+fn test_clock() {
+    use clock::DateTime;
+
+    let dt = DateTime::from_unix(0, 0);
+    assert_eq!((dt.year, dt.month, dt.day, dt.hour), (1970, 1, 1, 0));
+    // leap day
+    let dt = DateTime::from_unix(951_825_599, 999);
+    assert_eq!(dt.date_time_str(), "00-02-29 11:59:59");
+    assert_eq!(dt.time_str(), "11:59:59.999 ");
+    let dt = DateTime::from_unix(1_700_000_000, 0);
+    assert_eq!((dt.year, dt.month, dt.day), (2023, 11, 14));
+
+    // tests use the fixed time
+    let _lock = TERM_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut wmngr = WndMngr::new();
+    let wnds = AppWnds::create(&mut wmngr);
+    wmngr.show(wnds.main);
+    run_commands(&mut wmngr, &wnds);
+
+    let label = wgt::find_by_id(&tui_main_def::WND_MAIN_WGTS[..], id::LABEL_DATE).unwrap();
+    let mut txt = String::new();
+    wnds.main_wnd(&mut wmngr).get_label_text(label, &mut txt);
+    assert_eq!(txt, "Date•23-11-14 22:13:20");
+
+    // the label is refreshed every second
+    let now = timestamp_ms();
+    let expired = wmngr.timers.take_expired(now.wrapping_add(1000));
+    assert!(expired.contains(&(wnds.main, tui_main_state::TMR_CLOCK)));
+}
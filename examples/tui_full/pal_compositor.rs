@@ -0,0 +1,250 @@
+//! # Output compositor
+//!
+//! PAL wrapper keeping the model of the terminal screen: the output of the `rtwins::Term`
+//! is applied to the model, and only the changed cells are sent to the wrapped PAL,
+//! with the short cursor moves and the SGR emitted only when the style changes.
+//!
+//! DEC private modes (mouse, bracketed paste) and the size queries are passed as they are;
+//! the model size is limited, to fit in the target heap;
+//! the hyperlinks (OSC 8) are dropped, leaving the plain text
+
+use crate::vscreen::{self, Cell, VScreen};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Bytes produced by the `rtwins::Term`
+pub static BYTES_IN: AtomicU32 = AtomicU32::new(0);
+/// Bytes sent to the terminal
+pub static BYTES_OUT: AtomicU32 = AtomicU32::new(0);
+
+/// New screen size, `cols << 16 | rows`; 0 if not requested
+static RESIZE_REQ: AtomicU32 = AtomicU32::new(0);
+
+/// Largest screen model; on target, 90x24 cells take ~35kB of the heap
+#[cfg(target_os = "none")]
+const MAX_CELLS: usize = 90 * 24;
+#[cfg(not(target_os = "none"))]
+const MAX_CELLS: usize = 500 * 200;
+
+/// Returns the terminal size limited to the largest screen model;
+/// the UI should be laid out for this size, as nothing is drawn outside of the model
+pub fn fit_size(cols: u16, rows: u16) -> (u16, u16) {
+    let cols = (cols as usize).clamp(1, MAX_CELLS);
+    let rows = (rows as usize).clamp(1, MAX_CELLS / cols);
+    (cols as u16, rows as u16)
+}
+
+/// Makes the compositor resize the screen model with the next flush
+pub fn request_resize(cols: u16, rows: u16) {
+    let (cols, rows) = fit_size(cols, rows);
+    RESIZE_REQ.store((cols as u32) << 16 | rows as u32, Ordering::Relaxed);
+}
+
+/// Returns the before/after byte counts report
+pub fn stats_str() -> String {
+    let bin = BYTES_IN.load(Ordering::Relaxed);
+    let bout = BYTES_OUT.load(Ordering::Relaxed);
+    let ratio = if bin > 0 { bout as u64 * 100 / bin as u64 } else { 0 };
+    format!("Output: {} B drawn, {} B sent ({}%)", bin, bout, ratio)
+}
+
+/// Copies the sequences that do not modify the cells, but the terminal modes or state
+pub fn passthrough_seqs(s: &str, out: &mut String) {
+    let mut rest = s;
+    // the cursor position the size query is sent from
+    let mut last_cup = "";
+
+    while let Some(pos) = rest.find('\x1b') {
+        rest = &rest[pos..];
+
+        if rest.starts_with("\x1bc") {
+            out.push_str("\x1bc");
+            rest = &rest[2..];
+            continue;
+        }
+
+        if let Some(params) = rest.strip_prefix("\x1b[") {
+            if let Some(end) = params.find(|c: char| ('@'..='~').contains(&c)) {
+                let seq = &rest[..2 + end + 1];
+
+                if params.starts_with('?') && matches!(&params[end..end + 1], "h" | "l") {
+                    out.push_str(seq);
+                }
+                else if seq == "\x1b[6n" {
+                    // device status report - the size query - with the cursor move before it;
+                    // the cursor is restored, so the composed output is not affected
+                    out.push_str("\x1b7");
+                    out.push_str(last_cup);
+                    out.push_str(seq);
+                    out.push_str("\x1b8");
+                }
+                else if seq.ends_with('H') {
+                    last_cup = seq;
+                }
+
+                rest = &params[end + 1..];
+                continue;
+            }
+        }
+
+        rest = &rest[1..];
+    }
+}
+
+pub struct CompositorPal {
+    inner: Box<dyn rtwins::pal::Pal>,
+    line_buff: String,
+    /// model of what the terminal displays
+    screen: VScreen,
+    /// terminal cursor position; None if unknown
+    term_cursor: Option<(u16, u16)>,
+    /// terminal SGR state; None if unknown
+    term_style: Option<Cell>,
+}
+
+impl CompositorPal {
+    pub fn new(inner: Box<dyn rtwins::pal::Pal>, width: u16, height: u16) -> Self {
+        CompositorPal {
+            inner,
+            line_buff: String::with_capacity(500),
+            screen: VScreen::new(width, height),
+            term_cursor: None,
+            term_style: None,
+        }
+    }
+
+    /// Writes the changed cells to `out`
+    fn compose(&mut self, out: &mut String) {
+        let scr = &self.screen;
+        let (width, _) = scr.size();
+
+        for (col, row) in scr.dirty_cells() {
+            let cell = match scr.cell(col, row) {
+                Some(c) => *c,
+                None => continue,
+            };
+
+            let left_wide = col > 0
+                && scr
+                    .cell(col - 1, row)
+                    .map_or(false, |c| vscreen::char_width(c.ch) == 2);
+
+            let ch = if cell.ch != '\0' {
+                cell.ch
+            }
+            else if left_wide {
+                // right half of the wide character, already written
+                continue;
+            }
+            else {
+                // left half was overwritten
+                ' '
+            };
+
+            // cursor
+            match self.term_cursor {
+                Some((c, r)) if c == col && r == row => {}
+                Some((c, r)) if r == row && c < col && col - c <= 4 => {
+                    out.push_str(&format!("\x1b[{}C", col - c));
+                }
+                _ => out.push_str(&format!("\x1b[{};{}H", row + 1, col + 1)),
+            }
+
+            // style
+            if !self.term_style.map_or(false, |st| st.same_style(&cell)) {
+                out.push_str(&vscreen::sgr_encode(&cell));
+                self.term_style = Some(cell);
+            }
+
+            out.push(ch);
+
+            let next_col = col + vscreen::char_width(ch) as u16;
+            // at the last column, the terminal cursor position depends on the auto-wrap
+            self.term_cursor = (next_col < width).then_some((next_col, row));
+        }
+
+        self.screen.clear_dirty();
+
+        // leave the terminal in the state the `Term` expects
+        let (col, row) = self.screen.cursor();
+        if self.term_cursor != Some((col, row)) {
+            out.push_str(&format!("\x1b[{};{}H", row + 1, col + 1));
+            self.term_cursor = Some((col, row));
+        }
+
+        let style = *self.screen.style();
+        if !self.term_style.map_or(false, |st| st.same_style(&style)) {
+            out.push_str(&vscreen::sgr_encode(&style));
+            self.term_style = Some(style);
+        }
+    }
+}
+
+impl rtwins::pal::Pal for CompositorPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.line_buff.push(c);
+        }
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.line_buff.reserve(s.len() * repeat as usize);
+
+        for _ in 0..repeat {
+            self.line_buff.push_str(s);
+        }
+    }
+
+    fn flush_buff(&mut self) {
+        let req = RESIZE_REQ.swap(0, Ordering::Relaxed);
+        if req != 0 {
+            self.screen.resize((req >> 16) as u16, req as u16);
+            self.term_cursor = None;
+        }
+
+        if self.line_buff.is_empty() && req == 0 {
+            return;
+        }
+
+        let mut out = String::with_capacity(self.line_buff.len());
+        passthrough_seqs(&self.line_buff, &mut out);
+
+        if out.contains("\x1bc") {
+            // terminal reset
+            self.term_cursor = None;
+            self.term_style = None;
+        }
+
+        self.screen.write_str(&self.line_buff);
+        self.compose(&mut out);
+
+        BYTES_IN.fetch_add(self.line_buff.len() as u32, Ordering::Relaxed);
+        BYTES_OUT.fetch_add(out.len() as u32, Ordering::Relaxed);
+        self.line_buff.clear();
+
+        self.inner.write_str_n(&out, 1);
+        self.inner.flush_buff();
+    }
+
+    fn mark_logging(&mut self, active: bool) {
+        self.inner.mark_logging(active);
+    }
+
+    fn sleep(&self, ms: u16) {
+        self.inner.sleep(ms);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        self.inner.get_timestamp_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.inner.get_timespan_ms(prev_timestamp)
+    }
+}
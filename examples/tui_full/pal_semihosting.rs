@@ -1 +0,0 @@
-../tui_mini/pal_semihosting.rs
\ No newline at end of file
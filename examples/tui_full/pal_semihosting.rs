@@ -0,0 +1,182 @@
+//! # RTWins PAL for Cortex-M
+
+// use core::prelude::rust_2021::*;
+
+extern crate alloc;
+use alloc::string::String;
+
+use crate::clock;
+#[cfg(not(feature = "qemu"))]
+use crate::input_source::{self, InputError};
+use crate::input_source::{InputResult, InputSource};
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct SemihostingPal {
+    line_buff: String,
+}
+
+impl SemihostingPal {
+    pub fn new() -> Self {
+        SemihostingPal {
+            line_buff: String::with_capacity(100),
+        }
+    }
+}
+
+impl rtwins::pal::Pal for SemihostingPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.line_buff.push(c);
+        }
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.line_buff.reserve(s.len() * repeat as usize);
+
+        for _ in 0..repeat {
+            self.line_buff.push_str(s);
+        }
+
+        if self.line_buff.len() > 50 {
+            self.flush_buff();
+        }
+    }
+
+    fn flush_buff(&mut self) {
+        // hprint!("{}", self.line_buff);
+
+        if let Ok(ref mut out) = cortex_m_semihosting::hio::hstdout() {
+            let _ = out.write_all(self.line_buff.as_bytes());
+        }
+
+        self.line_buff.clear();
+        // self.sleep(50);
+    }
+
+    fn sleep(&self, ms: u16) {
+        clock::sleep_ms(ms as u32);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        clock::uptime_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        clock::elapsed_ms(prev_timestamp)
+    }
+}
+
+/// False if `InputSemiHost::read_input` blocks until the key is pressed, ignoring the timeout;
+/// then the timers, the animations and the toasts cannot work
+pub const INPUT_TIMEOUT_HONOURED: bool = cfg!(feature = "qemu");
+
+/// Bytes read at once; large enough for the whole escape sequences and short pastes
+#[cfg(not(feature = "qemu"))]
+const INPUT_BUFF_SIZE: usize = 64;
+
+/// Console input.
+///
+/// Semihosting has no call telling that the input is available, and the host `SYS_READ`
+/// blocks until the key is pressed, so on the hardware the timeout cannot be kept -
+/// see `INPUT_TIMEOUT_HONOURED`.
+/// Under QEMU, the `-nographic` console is also connected to the UART0,
+/// so the keys are taken from there, waiting with the SysTick timeout
+pub struct InputSemiHost {
+    #[cfg(feature = "qemu")]
+    uart: crate::pal_uart::InputUart,
+    #[cfg(not(feature = "qemu"))]
+    input_buff: [u8; INPUT_BUFF_SIZE],
+    #[cfg(not(feature = "qemu"))]
+    input_len: usize,
+    #[cfg(not(feature = "qemu"))]
+    stdin_fd: isize,
+}
+
+#[cfg(feature = "qemu")]
+impl InputSemiHost {
+    /// Createas a new console input reader, using the QEMU UART0
+    pub fn new() -> Self {
+        crate::pal_uart::uart0_init();
+
+        InputSemiHost {
+            uart: crate::pal_uart::InputUart::new(),
+        }
+    }
+}
+
+#[cfg(feature = "qemu")]
+impl InputSource for InputSemiHost {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        self.uart.read_input(timeout_ms)
+    }
+}
+
+#[cfg(not(feature = "qemu"))]
+impl InputSemiHost {
+    /// Createas a new Cortex-M semihosting input reader
+    pub fn new() -> Self {
+        let stdin_fd = unsafe {
+            cortex_m_semihosting::syscall!(OPEN, ":tt\0".as_ptr(),
+                cortex_m_semihosting::nr::open::R, 3) as isize
+        };
+
+        if stdin_fd == -1 {
+            rtwins::tr_err!("Unable to open stdin");
+        }
+
+        InputSemiHost {
+            input_buff: [0u8; INPUT_BUFF_SIZE],
+            input_len: 0,
+            stdin_fd,
+        }
+    }
+
+    fn hstdin(&mut self) -> usize {
+        // for ~3 seconds after start, the host returns nothing instead of blocking;
+        // later on, the call returns only when the key is pressed
+        let rc = unsafe {
+            // https://developer.arm.com/documentation/dui0471/e/semihosting/sys-read--0x06-
+            // READC - not implemented
+            let rc = cortex_m_semihosting::syscall!(READ,
+                self.stdin_fd, self.input_buff.as_mut_ptr(), self.input_buff.len());
+            // len -> 0 bytes read
+            // len-3 -> 3 bytes read
+            rc
+        };
+
+        // returns number of bytes read; rc greater than the buffer size means an error
+        self.input_buff.len().saturating_sub(rc)
+    }
+}
+
+#[cfg(not(feature = "qemu"))]
+impl InputSource for InputSemiHost {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        self.input_len = 0;
+
+        if self.stdin_fd == -1 {
+            return InputResult::Error(InputError::NotAvailable);
+        }
+
+        let started_at = clock::uptime_ms();
+
+        loop {
+            self.input_len = self.hstdin();
+
+            if self.input_len != 0 {
+                let seq = &self.input_buff[..self.input_len];
+
+                if input_source::is_quit_seq(seq, input_source::EOT_CODE) {
+                    return InputResult::Quit;
+                }
+
+                return InputResult::Data(seq);
+            }
+
+            if clock::elapsed_ms(started_at) >= timeout_ms as u32 {
+                return InputResult::Timeout;
+            }
+        }
+    }
+}
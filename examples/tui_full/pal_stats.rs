@@ -0,0 +1,100 @@
+//! # Output statistics
+//!
+//! PAL wrapper counting the bytes sent to the terminal, and the cost of the last frame:
+//! all the output between `frame_begin()` and `frame_end()`
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use rtwins::TERM;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+
+// ---------------------------------------------------------------------------------------------- //
+
+static BYTES: AtomicU32 = AtomicU32::new(0);
+static FLUSHES: AtomicU32 = AtomicU32::new(0);
+static FRAME_BYTES: AtomicU32 = AtomicU32::new(0);
+static FRAME_MS: AtomicU32 = AtomicU32::new(0);
+static FRAME_START_BYTES: AtomicU32 = AtomicU32::new(0);
+static FRAME_START_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Starts the frame measurement; must be called with the `TERM` unlocked
+pub fn frame_begin() {
+    let ts = TERM.try_lock().map_or(0, |term| term.pal.get_timestamp_ms());
+    FRAME_START_MS.store(ts, Ordering::Relaxed);
+    FRAME_START_BYTES.store(BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Ends the frame measurement; frames without the output are not taken into account
+pub fn frame_end() {
+    let bytes = BYTES
+        .load(Ordering::Relaxed)
+        .wrapping_sub(FRAME_START_BYTES.load(Ordering::Relaxed));
+
+    if bytes > 0 {
+        let start_ms = FRAME_START_MS.load(Ordering::Relaxed);
+        let ms = TERM.try_lock().map_or(0, |term| term.pal.get_timespan_ms(start_ms));
+        FRAME_BYTES.store(bytes, Ordering::Relaxed);
+        FRAME_MS.store(ms, Ordering::Relaxed);
+    }
+}
+
+/// Returns the one-line statistics summary
+pub fn summary() -> String {
+    format!(
+        " Out: {} B in {} flushes • Last frame: {} B, {} ms",
+        BYTES.load(Ordering::Relaxed),
+        FLUSHES.load(Ordering::Relaxed),
+        FRAME_BYTES.load(Ordering::Relaxed),
+        FRAME_MS.load(Ordering::Relaxed)
+    )
+}
+
+pub struct StatsPal {
+    inner: Box<dyn rtwins::pal::Pal>,
+    /// bytes written since the last flush
+    buffered: u32,
+}
+
+impl StatsPal {
+    pub fn new(inner: Box<dyn rtwins::pal::Pal>) -> Self {
+        StatsPal { inner, buffered: 0 }
+    }
+}
+
+impl rtwins::pal::Pal for StatsPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        self.buffered += (c.len_utf8() * repeat.max(0) as usize) as u32;
+        self.inner.write_char_n(c, repeat);
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.buffered += (s.len() * repeat.max(0) as usize) as u32;
+        self.inner.write_str_n(s, repeat);
+    }
+
+    fn flush_buff(&mut self) {
+        BYTES.fetch_add(self.buffered, Ordering::Relaxed);
+        FLUSHES.fetch_add(1, Ordering::Relaxed);
+        self.buffered = 0;
+        self.inner.flush_buff();
+    }
+
+    fn mark_logging(&mut self, active: bool) {
+        self.inner.mark_logging(active);
+    }
+
+    fn sleep(&self, ms: u16) {
+        self.inner.sleep(ms);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        self.inner.get_timestamp_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.inner.get_timespan_ms(prev_timestamp)
+    }
+}
@@ -6,7 +6,6 @@ use std::io::Write;
 
 pub struct DemoPal {
     line_buff: String,
-    writing_logs: bool,
     started_at: std::time::Instant,
 }
 
@@ -14,7 +13,6 @@ impl DemoPal {
     pub fn new() -> Self {
         DemoPal {
             line_buff: String::with_capacity(500),
-            writing_logs: false,
             started_at: std::time::Instant::now(),
         }
     }
@@ -47,17 +45,6 @@ impl rtwins::pal::Pal for DemoPal {
             .expect("Error flushing stdout");
 
         self.line_buff.clear();
-
-        #[cfg(feature = "slow_flush")]
-        self.sleep(50); // helpful when debugging drawing process
-    }
-
-    fn mark_logging(&mut self, active: bool) {
-        if self.writing_logs && !active {
-            // write to logs.txt
-        }
-
-        self.writing_logs = active;
     }
 
     fn sleep(&self, ms: u16) {
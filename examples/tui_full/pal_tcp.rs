@@ -0,0 +1,275 @@
+//! # RTWins PAL and input over the TCP socket
+//!
+//! Serves the UI to a telnet client, eg. `telnet localhost 2323`:
+//! the client is switched to the character mode with the local echo off
+//! and reports its window size (NAWS)
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+
+use crate::input_source::{self, InputError, InputResult, InputSource};
+
+// ---------------------------------------------------------------------------------------------- //
+
+// https://www.rfc-editor.org/rfc/rfc854
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const OPT_ECHO: u8 = 1;
+const OPT_SUPPRESS_GO_AHEAD: u8 = 3;
+const OPT_NAWS: u8 = 31;
+
+/// Server echoes (so the client does not), no go-ahead (character mode), report the size
+#[rustfmt::skip]
+const NEGOTIATION: &[u8] = &[
+    IAC, WILL, OPT_ECHO,
+    IAC, WILL, OPT_SUPPRESS_GO_AHEAD,
+    IAC, DO, OPT_SUPPRESS_GO_AHEAD,
+    IAC, DO, OPT_NAWS,
+];
+
+/// Listening socket, bound to the loopback interface only
+pub struct TcpServer {
+    listener: TcpListener,
+}
+
+impl TcpServer {
+    /// Binds the port; 0 means any free port
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        Ok(TcpServer { listener })
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |addr| addr.port())
+    }
+
+    /// Waits for the client and starts the telnet session
+    pub fn accept(&self) -> std::io::Result<(TcpPal, InputTcp)> {
+        let (mut stream, _addr) = self.listener.accept()?;
+        stream.set_nodelay(true)?;
+        stream.write_all(NEGOTIATION)?;
+
+        let pal = TcpPal {
+            line_buff: String::with_capacity(500),
+            stream: stream.try_clone()?,
+            started_at: std::time::Instant::now(),
+        };
+
+        let inp = InputTcp {
+            stream,
+            state: TelnetState::Data,
+            subneg: Vec::with_capacity(8),
+            data: Vec::with_capacity(64),
+            out: Vec::with_capacity(64),
+            resized: None,
+        };
+
+        Ok((pal, inp))
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct TcpPal {
+    line_buff: String,
+    stream: TcpStream,
+    started_at: std::time::Instant,
+}
+
+impl Drop for TcpPal {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl rtwins::pal::Pal for TcpPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.line_buff.push(c);
+        }
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.line_buff.reserve(s.len() * repeat as usize);
+
+        for _ in 0..repeat {
+            self.line_buff.push_str(s);
+        }
+    }
+
+    fn flush_buff(&mut self) {
+        // UTF-8 never contains the 0xFF, so there is no IAC to escape;
+        // when the client is gone, the input reports it
+        let _ = self.stream.write_all(self.line_buff.as_bytes());
+        self.line_buff.clear();
+    }
+
+    fn sleep(&self, ms: u16) {
+        std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        let dif = std::time::Instant::now() - self.started_at;
+        dif.as_millis() as u32
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.get_timestamp_ms().wrapping_sub(prev_timestamp)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Telnet stream parser state
+#[derive(Clone, Copy, PartialEq)]
+enum TelnetState {
+    Data,
+    /// after CR, that the client sends as CR NUL or CR LF
+    Cr,
+    Iac,
+    /// after WILL/WONT/DO/DONT, the option code is expected
+    Opt,
+    /// subnegotiation
+    Sb,
+    SbIac,
+}
+
+pub struct InputTcp {
+    stream: TcpStream,
+    state: TelnetState,
+    /// subnegotiation parameters
+    subneg: Vec<u8>,
+    /// received input, with the telnet commands removed
+    data: Vec<u8>,
+    /// input returned by the last call
+    out: Vec<u8>,
+    /// window size reported by the client, (cols, rows)
+    resized: Option<(u16, u16)>,
+}
+
+impl InputTcp {
+    /// Reads the socket; None if some bytes were parsed
+    fn read_stream(&mut self, timeout_ms: u16) -> Option<InputResult<'static>> {
+        // zero would mean: blocking
+        let timeout = std::time::Duration::from_millis(timeout_ms.max(1) as u64);
+        let _ = self.stream.set_read_timeout(Some(timeout));
+
+        let mut buff = [0u8; 256];
+
+        match self.stream.read(&mut buff) {
+            Ok(0) => {
+                rtwins::tr_info!("Telnet client disconnected");
+                Some(InputResult::Quit)
+            }
+            Ok(nb) => {
+                buff[..nb].iter().for_each(|b| self.parse(*b));
+                None
+            }
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                Some(InputResult::Timeout)
+            }
+            Err(e) => {
+                let code = e.raw_os_error().unwrap_or(-1);
+                Some(InputResult::Error(InputError::Read(code)))
+            }
+        }
+    }
+
+    fn parse(&mut self, b: u8) {
+        self.state = match self.state {
+            TelnetState::Data | TelnetState::Cr => {
+                if self.state == TelnetState::Cr && (b == 0 || b == b'\n') {
+                    TelnetState::Data
+                }
+                else if b == IAC {
+                    TelnetState::Iac
+                }
+                else {
+                    self.data.push(b);
+                    rtwins::tetrary!(b == b'\r', TelnetState::Cr, TelnetState::Data)
+                }
+            }
+            TelnetState::Iac => match b {
+                IAC => {
+                    self.data.push(IAC);
+                    TelnetState::Data
+                }
+                WILL | WONT | DO | DONT => TelnetState::Opt,
+                SB => {
+                    self.subneg.clear();
+                    TelnetState::Sb
+                }
+                // NOP, GA...
+                _ => TelnetState::Data,
+            },
+            TelnetState::Opt => TelnetState::Data,
+            TelnetState::Sb => {
+                if b == IAC {
+                    TelnetState::SbIac
+                }
+                else {
+                    self.subneg.push(b);
+                    TelnetState::Sb
+                }
+            }
+            TelnetState::SbIac => match b {
+                SE => {
+                    self.subnegotiation_end();
+                    TelnetState::Data
+                }
+                IAC => {
+                    self.subneg.push(IAC);
+                    TelnetState::Sb
+                }
+                _ => TelnetState::Data,
+            },
+        };
+    }
+
+    fn subnegotiation_end(&mut self) {
+        // NAWS: width and height, 16 bit each, big endian
+        if let [OPT_NAWS, w1, w0, h1, h0, ..] = self.subneg[..] {
+            let cols = u16::from_be_bytes([w1, w0]);
+            let rows = u16::from_be_bytes([h1, h0]);
+            self.resized = Some((cols, rows));
+        }
+    }
+}
+
+impl InputSource for InputTcp {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        if self.data.is_empty() && self.resized.is_none() {
+            if let Some(res) = self.read_stream(timeout_ms) {
+                return res;
+            }
+        }
+
+        // the size first, the keys with the next call
+        if let Some((cols, rows)) = self.resized.take() {
+            return InputResult::Resized { cols, rows };
+        }
+
+        if self.data.is_empty() {
+            // only the telnet commands received
+            return InputResult::Timeout;
+        }
+
+        self.out = core::mem::take(&mut self.data);
+
+        if input_source::is_quit_seq(&self.out, input_source::EOT_CODE) {
+            InputResult::Quit
+        }
+        else {
+            InputResult::Data(&self.out)
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! # RTWins PAL for the LM3S6965 UART0
+//!
+//! QEMU exposes the UART0 of the `lm3s6965evb` machine with `-serial stdio`,
+//! so the demo can run without semihosting at all
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+
+extern crate alloc;
+use alloc::string::String;
+
+use crate::clock;
+use crate::input_source::{self, InputResult, InputSource};
+
+// ---------------------------------------------------------------------------------------------- //
+
+// https://www.ti.com/lit/ds/symlink/lm3s6965.pdf
+const SYSCTL_RCGC1: usize = 0x400F_E104;
+const SYSCTL_RCGC2: usize = 0x400F_E108;
+const GPIOA_AFSEL: usize = 0x4000_4420;
+const GPIOA_DEN: usize = 0x4000_451C;
+
+const UART0_BASE: usize = 0x4000_C000;
+const UART_DR: usize = UART0_BASE + 0x000;
+const UART_FR: usize = UART0_BASE + 0x018;
+const UART_IBRD: usize = UART0_BASE + 0x024;
+const UART_FBRD: usize = UART0_BASE + 0x028;
+const UART_LCRH: usize = UART0_BASE + 0x02C;
+const UART_CTL: usize = UART0_BASE + 0x030;
+const UART_IM: usize = UART0_BASE + 0x038;
+const UART_ICR: usize = UART0_BASE + 0x044;
+
+const UART_FR_RXFE: u32 = 1 << 4;
+const UART_FR_TXFF: u32 = 1 << 5;
+const UART_LCRH_FEN: u32 = 1 << 4;
+const UART_LCRH_WLEN_8: u32 = 0x3 << 5;
+const UART_CTL_UARTEN: u32 = 1 << 0;
+const UART_CTL_TXE: u32 = 1 << 8;
+const UART_CTL_RXE: u32 = 1 << 9;
+const UART_INT_RX: u32 = 1 << 4;
+const UART_INT_RT: u32 = 1 << 6;
+
+const UART0_IRQN: i16 = 5;
+const UART_BAUDRATE: u32 = 115_200;
+
+const RX_RING_SIZE: usize = 256;
+
+#[derive(Clone, Copy)]
+struct Uart0Irq;
+
+// SAFETY: 5 is the UART0 interrupt number of the LM3S6965
+unsafe impl cortex_m::interrupt::InterruptNumber for Uart0Irq {
+    fn number(self) -> u16 {
+        UART0_IRQN as u16
+    }
+}
+
+#[inline]
+fn reg_read(addr: usize) -> u32 {
+    // SAFETY: only the valid LM3S6965 register addresses are used
+    unsafe { core::ptr::read_volatile(addr as *const u32) }
+}
+
+#[inline]
+fn reg_write(addr: usize, val: u32) {
+    // SAFETY: only the valid LM3S6965 register addresses are used
+    unsafe { core::ptr::write_volatile(addr as *mut u32, val) }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Bytes received in the UART interrupt, waiting to be read by the `InputUart`
+struct RxRing {
+    buff: [u8; RX_RING_SIZE],
+    head: usize,
+    tail: usize,
+}
+
+impl RxRing {
+    const fn new() -> Self {
+        RxRing {
+            buff: [0u8; RX_RING_SIZE],
+            head: 0,
+            tail: 0,
+        }
+    }
+
+    fn push(&mut self, b: u8) {
+        let next = (self.head + 1) % RX_RING_SIZE;
+        // on overflow, the newest byte is dropped
+        if next != self.tail {
+            self.buff[self.head] = b;
+            self.head = next;
+        }
+    }
+
+    fn pop_into(&mut self, out: &mut [u8]) -> usize {
+        let mut n = 0;
+
+        while n < out.len() && self.tail != self.head {
+            out[n] = self.buff[self.tail];
+            self.tail = (self.tail + 1) % RX_RING_SIZE;
+            n += 1;
+        }
+
+        n
+    }
+}
+
+static RX_RING: Mutex<RefCell<RxRing>> = Mutex::new(RefCell::new(RxRing::new()));
+
+/// Without the device crate, all the interrupts end up here
+#[cortex_m_rt::exception]
+unsafe fn DefaultHandler(irqn: i16) {
+    if irqn == UART0_IRQN {
+        cortex_m::interrupt::free(|cs| {
+            let mut ring = RX_RING.borrow(cs).borrow_mut();
+
+            while reg_read(UART_FR) & UART_FR_RXFE == 0 {
+                ring.push(reg_read(UART_DR) as u8);
+            }
+        });
+
+        reg_write(UART_ICR, UART_INT_RX | UART_INT_RT);
+    }
+}
+
+/// Configure UART0 as 8N1 with RX interrupt
+pub fn uart0_init() {
+    // enable the UART0 and GPIOA clocks
+    reg_write(SYSCTL_RCGC1, reg_read(SYSCTL_RCGC1) | 1);
+    reg_write(SYSCTL_RCGC2, reg_read(SYSCTL_RCGC2) | 1);
+    // PA0, PA1 as U0Rx, U0Tx
+    reg_write(GPIOA_AFSEL, reg_read(GPIOA_AFSEL) | 0x03);
+    reg_write(GPIOA_DEN, reg_read(GPIOA_DEN) | 0x03);
+
+    reg_write(UART_CTL, 0);
+    // BRD = CORE_CLOCK / (16 * BAUDRATE), fractional part in 1/64; the UART runs on the core clock
+    let brd_x64 = (clock::CORE_CLOCK_HZ * 4) / UART_BAUDRATE;
+    reg_write(UART_IBRD, brd_x64 / 64);
+    reg_write(UART_FBRD, brd_x64 % 64);
+    reg_write(UART_LCRH, UART_LCRH_WLEN_8 | UART_LCRH_FEN);
+    reg_write(UART_IM, UART_INT_RX | UART_INT_RT);
+    reg_write(UART_CTL, UART_CTL_UARTEN | UART_CTL_TXE | UART_CTL_RXE);
+
+    // SAFETY: the handler and the RX_RING are ready to use
+    unsafe {
+        cortex_m::peripheral::NVIC::unmask(Uart0Irq);
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct UartPal {
+    line_buff: String,
+}
+
+impl UartPal {
+    pub fn new() -> Self {
+        uart0_init();
+
+        UartPal {
+            line_buff: String::with_capacity(100),
+        }
+    }
+}
+
+impl rtwins::pal::Pal for UartPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.line_buff.push(c);
+        }
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.line_buff.reserve(s.len() * repeat as usize);
+
+        for _ in 0..repeat {
+            self.line_buff.push_str(s);
+        }
+    }
+
+    fn flush_buff(&mut self) {
+        for b in self.line_buff.as_bytes() {
+            while reg_read(UART_FR) & UART_FR_TXFF != 0 {}
+            reg_write(UART_DR, *b as u32);
+        }
+
+        self.line_buff.clear();
+    }
+
+    fn sleep(&self, ms: u16) {
+        clock::sleep_ms(ms as u32);
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        clock::uptime_ms()
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        clock::elapsed_ms(prev_timestamp)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct InputUart {
+    /// can take the whole ring content at once
+    input_buff: [u8; RX_RING_SIZE],
+    input_len: usize,
+}
+
+impl InputUart {
+    /// Createas a new UART0 input reader; the `UartPal` must be created first,
+    /// or the `uart0_init()` called
+    pub fn new() -> Self {
+        InputUart {
+            input_buff: [0u8; RX_RING_SIZE],
+            input_len: 0,
+        }
+    }
+}
+
+impl InputSource for InputUart {
+    fn read_input(&mut self, timeout_ms: u16) -> InputResult<'_> {
+        let started_at = clock::uptime_ms();
+        self.input_len = 0;
+
+        loop {
+            let buff = &mut self.input_buff;
+            let timed_out = clock::elapsed_ms(started_at) >= timeout_ms as u32;
+
+            self.input_len = cortex_m::interrupt::free(|cs| {
+                let n = RX_RING.borrow(cs).borrow_mut().pop_into(buff);
+                if n == 0 && !timed_out {
+                    // pending UART or SysTick interrupt wakes the core even with interrupts masked
+                    cortex_m::asm::wfi();
+                }
+                n
+            });
+
+            if self.input_len != 0 {
+                break;
+            }
+
+            if timed_out {
+                return InputResult::Timeout;
+            }
+        }
+
+        let seq = &self.input_buff[..self.input_len];
+
+        if input_source::is_quit_seq(seq, input_source::EOT_CODE) {
+            InputResult::Quit
+        }
+        else {
+            InputResult::Data(seq)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! # RTWins headless PAL
+//!
+//! Renders into the virtual screen instead of the terminal,
+//! so the tests can check what the windows look like
+
+#![allow(dead_code)]
+
+use crate::vscreen::VScreen;
+use std::sync::{Arc, Mutex};
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct VScreenPal {
+    line_buff: String,
+    screen: Arc<Mutex<VScreen>>,
+    started_at: std::time::Instant,
+}
+
+impl VScreenPal {
+    /// Creates the PAL and the shared screen, to be queried after drawing
+    pub fn new(width: u16, height: u16) -> (Self, Arc<Mutex<VScreen>>) {
+        let screen = Arc::new(Mutex::new(VScreen::new(width, height)));
+
+        let pal = VScreenPal {
+            line_buff: String::with_capacity(500),
+            screen: Arc::clone(&screen),
+            started_at: std::time::Instant::now(),
+        };
+
+        (pal, screen)
+    }
+}
+
+impl rtwins::pal::Pal for VScreenPal {
+    fn write_char_n(&mut self, c: char, repeat: i16) {
+        for _ in 0..repeat {
+            self.line_buff.push(c);
+        }
+    }
+
+    fn write_str_n(&mut self, s: &str, repeat: i16) {
+        self.line_buff.reserve(s.len() * repeat as usize);
+
+        for _ in 0..repeat {
+            self.line_buff.push_str(s);
+        }
+    }
+
+    fn flush_buff(&mut self) {
+        if let Ok(mut scr) = self.screen.lock() {
+            scr.write_str(&self.line_buff);
+        }
+
+        self.line_buff.clear();
+    }
+
+    fn sleep(&self, _ms: u16) {
+        // headless rendering does not need to wait
+    }
+
+    fn get_timestamp_ms(&self) -> u32 {
+        let dif = std::time::Instant::now() - self.started_at;
+        dif.as_millis() as u32
+    }
+
+    fn get_timespan_ms(&self, prev_timestamp: u32) -> u32 {
+        self.get_timestamp_ms().wrapping_sub(prev_timestamp)
+    }
+}
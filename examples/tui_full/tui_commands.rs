@@ -2,21 +2,78 @@
 
 use rtwins::wgt::WId;
 
+use super::tui_timers::TimerToken;
+
 extern crate alloc;
-use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
 
 // ---------------------------------------------------------------------------------------------- //
 
+/// Identifies the dialog request, so its owner can tell the results apart
+pub type DialogToken = u16;
+
+/// Outcome of the dialog
+#[derive(Clone, Debug, PartialEq)]
+pub enum DialogResult {
+    Yes,
+    No,
+    Ok,
+    Cancel,
+    /// closed with Esc
+    Closed,
+    /// text entered in the input dialog
+    Text(String),
+}
+
+/// Toast notification importance; selects its style
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
 pub enum Command {
+    /// Shows a new popup, on top of the visible windows;
+    /// the result is sent back to the `owner` window, if any
     ShowPopup {
         title: String,
         message: String,
         buttons: &'static str,
-        on_button: Box<dyn Fn(WId) + Send>,
+        owner: Option<WId>,
+        token: DialogToken,
+    },
+    /// Shows the input dialog, on top of the visible windows;
+    /// the entered text is sent back to the `owner` window, if any, as `DialogResult::Text`
+    ShowInputDialog {
+        title: String,
+        prompt: String,
+        /// initial text
+        value: String,
+        /// if set, only the integer numbers in given range are accepted
+        numeric: Option<(i64, i64)>,
+        owner: Option<WId>,
+        token: DialogToken,
+    },
+    /// Shows the non-modal notification, that disappears after a while
+    ShowToast { severity: Severity, message: String },
+    /// Starts the timer of the `owner` window; restarts it if already running
+    StartTimer {
+        owner: WId,
+        token: TimerToken,
+        interval_ms: u32,
+        periodic: bool,
+    },
+    StopTimer { owner: WId, token: TimerToken },
+    /// Closes the popup window
+    HidePopup { wnd_id: WId },
+    /// Delivers the dialog outcome to the window that requested the dialog
+    DialogResult {
+        owner: Option<WId>,
+        token: DialogToken,
+        result: DialogResult,
     },
-    HidePopup,
 }
 
 /// Deferred commands
@@ -0,0 +1,62 @@
+//! Help window - widgets definition
+
+use rtwins::colors::{ColorBg, ColorFg};
+use rtwins::common::*;
+use rtwins::wgt::prop;
+use rtwins::wgt::*;
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub mod idhlp {
+    use rtwins::wgt::{WId, WIDGET_ID_NONE};
+
+    #[rustfmt::skip]
+    rtwins::generate_ids!(
+        WND_HELP
+            TBX_HELP
+            LBL_HINT
+    );
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+#[rustfmt::skip]
+const WINDOW_HELP: Widget = Widget {
+    id: idhlp::WND_HELP,
+    link: Link::cdeflt(),
+    coord: Coord::cdeflt(),
+    size: Size { width: 52, height: 16 },
+    prop: prop::Window {
+        title: "",
+        fg_color: ColorFg::White,
+        bg_color: ColorBg::Blue,
+        is_popup: true,
+    }.into(),
+    children: &[
+        Widget {
+            id: idhlp::TBX_HELP,
+            coord: Coord { col: 1, row: 1 },
+            size: Size { width: 50, height: 12 },
+            prop: prop::TextBox {
+                fg_color: ColorFg::White,
+                bg_color: ColorBg::BlueIntense,
+            }.into(),
+            ..Widget::cdeflt()
+        },
+        Widget {
+            id: idhlp::LBL_HINT,
+            coord: Coord { col: 2, row: 14 },
+            size: Size { width: 48, height: 1 },
+            prop: prop::Label {
+                title: "Up/Down, PgUp/PgDown - scroll   Esc - close",
+                fg_color: ColorFg::Inherit,
+                bg_color: ColorBg::Inherit,
+            }.into(),
+            ..Widget::cdeflt()
+        },
+    ]
+};
+
+/// Help window widgets tree, translated into the array
+pub const WND_HELP_WGTS: [Widget; transform::tree_wgt_count(&WINDOW_HELP)] =
+    transform::tree_to_array(&WINDOW_HELP);
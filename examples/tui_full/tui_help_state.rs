@@ -0,0 +1,200 @@
+//! Help window - window state
+
+#![allow(unused_variables)]
+#![allow(dead_code)]
+
+use rtwins::common::*;
+use rtwins::esc;
+use rtwins::input;
+use rtwins::input::*;
+use rtwins::utils;
+use rtwins::wgt::{self, rstate, WId, Widget, WindowState};
+use rtwins::TERM;
+use rtwins::*;
+
+use super::tui_commands::*;
+use super::tui_help_def::idhlp;
+use super::tui_keybindings::{KeyBindings, LISTBOX_KEYS};
+use super::tui_wnd_mngr::{close_popup, AppWindow, PopupPlacement};
+
+use core::cell::RefCell;
+
+extern crate alloc;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Width of the keys column
+const KEYS_WIDTH: usize = 20;
+
+/// State of the help popup, listing the shortcuts
+pub struct HelpState {
+    // id of the window
+    pub wnd_id: WId,
+    /// all window widgets, starting with the window widget itself
+    widgets: &'static [wgt::Widget],
+    /// widgets runtime state
+    pub rs: wgt::RuntimeStates,
+    /// currently focused widget
+    focused_id: WId,
+    /// list of widgets to redraw
+    invalidated: Vec<WId>,
+    /// popup location, centered over main window
+    pub placement: PopupPlacement,
+    /// help text, one entry per line
+    lines: utils::StringListRc,
+    // app-wide commands queue
+    cmds: Rc<RefCell<CommandsQueue>>,
+}
+
+impl HelpState {
+    pub fn new(wnd_id: WId, widgets: &'static [Widget], cmds: Rc<RefCell<CommandsQueue>>) -> Self {
+        HelpState {
+            wnd_id,
+            widgets,
+            rs: wgt::RuntimeStates::default(),
+            focused_id: idhlp::TBX_HELP,
+            invalidated: Vec::with_capacity(4),
+            placement: PopupPlacement::new(&widgets[0]),
+            lines: Arc::new(RefCell::new(Vec::new())),
+            cmds,
+        }
+    }
+
+    /// Fills the help text with the active shortcuts
+    pub fn setup(&mut self, keybindings: &KeyBindings) {
+        let mut lines = Vec::new();
+
+        lines.push(format!("{}Global shortcuts{}", esc::BOLD, esc::NORMAL));
+        for (keys, descr) in keybindings.describe() {
+            lines.push(format!(" {:w$} {}", keys, descr, w = KEYS_WIDTH));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("{}List box{}", esc::BOLD, esc::NORMAL));
+        for (keys, descr) in LISTBOX_KEYS.iter() {
+            lines.push(format!(" {:w$} {}", keys, descr, w = KEYS_WIDTH));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("{}Popups{}", esc::BOLD, esc::NORMAL));
+        lines.push(format!(" {:w$} {}", "Esc", "close", w = KEYS_WIDTH));
+
+        self.lines = Arc::new(RefCell::new(lines));
+        self.rs.txtbx.entry(idhlp::TBX_HELP).or_default().top_line = 0;
+    }
+
+    /// Help text lines
+    pub fn get_lines(&self) -> utils::StringListRc {
+        Arc::clone(&self.lines)
+    }
+}
+
+// -----------------------------------------------------------------------------------------------
+
+impl AppWindow for HelpState {
+    fn on_screen_resize(&mut self, cols: u16, rows: u16) {
+        self.placement.on_screen_resize(cols, rows);
+    }
+}
+
+impl rtwins::wgt::WindowState for HelpState {
+    /** events **/
+
+    fn on_text_box_scroll(&mut self, wgt: &Widget, top_line: i16) {
+        let rs = self.rs.txtbx.entry(wgt.id).or_default();
+        rs.top_line = top_line;
+    }
+
+    fn on_window_unhandled_input_evt(&mut self, wgt: &Widget, ii: &InputInfo) -> bool {
+        rtwins::tr_debug!("on_window_unhandled_input_evt={}", ii.name);
+        if let InputEvent::Key(input::Key::Esc) = ii.evnt {
+            close_popup(&self.cmds, self.wnd_id, None, 0, DialogResult::Closed);
+            return true;
+        }
+
+        false
+    }
+
+    /** common state queries **/
+
+    fn is_enabled(&self, wgt: &Widget) -> bool {
+        self.rs.get_enabled_or_default(wgt.id)
+    }
+
+    fn is_focused(&self, wgt: &Widget) -> bool {
+        self.focused_id == wgt.id
+    }
+
+    fn get_focused_id(&mut self) -> WId {
+        self.focused_id
+    }
+
+    fn set_focused_id(&mut self, wid: WId) {
+        self.focused_id = wid;
+    }
+
+    fn get_widgets(&self) -> &'static [Widget] {
+        self.widgets
+    }
+
+    fn get_rstate(&mut self) -> Option<&mut wgt::RuntimeStates> {
+        Some(&mut self.rs)
+    }
+
+    /** widget-specific queries; all mutable params are outputs **/
+
+    fn get_window_coord(&mut self) -> Coord {
+        self.placement.coord
+    }
+
+    fn get_window_size(&mut self) -> Size {
+        self.widgets.first().unwrap().size
+    }
+
+    fn get_window_title(&mut self, wgt: &Widget, out: &mut String) {
+        out.push_str("Help");
+    }
+
+    fn get_text_box_state(&mut self, wgt: &Widget, out: &mut rstate::TxtbxState) {
+        let rs = self.rs.txtbx.entry(wgt.id).or_default();
+        out.top_line = rs.top_line;
+
+        if wgt.id == idhlp::TBX_HELP {
+            out.lines = Arc::clone(&self.lines);
+        }
+    }
+
+    /* requests */
+
+    fn invalidate_many(&mut self, wids: &[WId]) {
+        // iterate, to avoid adding the same ID twice
+        for wid in wids.iter() {
+            if !self.invalidated.contains(wid) {
+                self.invalidated.push(*wid);
+            }
+        }
+    }
+
+    fn instant_redraw(&mut self, wid: WId) {
+        if let Some(mut term_guard) = TERM.try_lock() {
+            term_guard.draw(self, &[wid]);
+            term_guard.flush_buff();
+        }
+        else {
+            rtwins::tr_warn!("Cannot lock the term");
+        }
+    }
+
+    fn clear_invalidated(&mut self) {
+        self.invalidated.clear();
+    }
+
+    fn get_invalidated(&mut self, out: &mut Vec<WId>) {
+        core::mem::swap(&mut self.invalidated, out);
+    }
+}
@@ -0,0 +1,89 @@
+//! Input dialog - widgets definition
+
+use rtwins::colors::{ColorBg, ColorFg};
+use rtwins::common::*;
+use rtwins::wgt::prop;
+use rtwins::wgt::*;
+
+use super::tui_colors::*;
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub mod idinp {
+    use rtwins::wgt::{WId, WIDGET_ID_NONE};
+
+    #[rustfmt::skip]
+    rtwins::generate_ids!(
+        WND_INPUTDLG
+            LBL_PROMPT
+            EDIT_VALUE
+            BTN_OK
+            BTN_CANCEL
+    );
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+#[rustfmt::skip]
+const WINDOW_INPUTDLG: Widget = Widget {
+    id: idinp::WND_INPUTDLG,
+    link: Link::cdeflt(),
+    coord: Coord::cdeflt(),
+    size: Size { width: 34, height: 9 },
+    prop: prop::Window {
+        title: "",
+        fg_color: ColorFg::Blue,
+        bg_color: ColorBg::White,
+        is_popup: true,
+    }.into(),
+    children: &[
+        Widget {
+            id: idinp::LBL_PROMPT,
+            coord: Coord { col: 2, row: 1 },
+            size: Size { width: 30, height: 2 },
+            prop: prop::Label {
+                title: "",
+                fg_color: ColorFg::Inherit,
+                bg_color: ColorBg::Inherit,
+            }.into(),
+            ..Widget::cdeflt()
+        },
+        Widget {
+            id: idinp::EDIT_VALUE,
+            coord: Coord { col: 2, row: 4 },
+            size: Size { width: 30, height: 1 },
+            prop: prop::TextEdit {
+                fg_color: ColorFg::Black,
+                bg_color: ColorBgTheme::Edit1.into(),
+                psw_mask: false,
+            }.into(),
+            ..Widget::cdeflt()
+        },
+        Widget {
+            id: idinp::BTN_OK,
+            coord: Coord { col: 8, row: 6 },
+            prop: prop::Button {
+                text: "OK",
+                fg_color: ColorFgTheme::ButtonGreen.into(),
+                bg_color: ColorBgTheme::ButtonGreen.into(),
+                style: ButtonStyle::Solid
+            }.into(),
+            ..Widget::cdeflt()
+        },
+        Widget {
+            id: idinp::BTN_CANCEL,
+            coord: Coord { col: 16, row: 6 },
+            prop: prop::Button {
+                text: "CANCEL",
+                fg_color: ColorFg::White,
+                bg_color: ColorBg::BlackIntense,
+                style: ButtonStyle::Solid
+            }.into(),
+            ..Widget::cdeflt()
+        },
+    ]
+};
+
+/// Input dialog widgets tree, translated into the array
+pub const WND_INPUTDLG_WGTS: [Widget; transform::tree_wgt_count(&WINDOW_INPUTDLG)] =
+    transform::tree_to_array(&WINDOW_INPUTDLG);
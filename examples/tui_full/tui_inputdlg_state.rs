@@ -0,0 +1,258 @@
+//! Input dialog - window state
+
+#![allow(unused_variables)]
+#![allow(dead_code)]
+
+use rtwins::common::*;
+use rtwins::input;
+use rtwins::input::*;
+use rtwins::utils;
+use rtwins::wgt::{self, WId, Widget, WindowState};
+use rtwins::TERM;
+use rtwins::*;
+
+use super::tui_commands::*;
+use super::tui_inputdlg_def::idinp;
+use super::tui_wnd_mngr::{close_popup, AppWindow, PopupPlacement};
+
+use core::cell::RefCell;
+
+extern crate alloc;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// State of the "enter a value" popup
+pub struct InputDlgState {
+    // id of the window
+    pub wnd_id: WId,
+    /// all window widgets, starting with the window widget itself
+    widgets: &'static [wgt::Widget],
+    /// widgets runtime state
+    pub rs: wgt::RuntimeStates,
+    /// currently focused widget
+    focused_id: WId,
+    /// list of widgets to redraw
+    invalidated: Vec<WId>,
+    /// popup location, centered over main window
+    pub placement: PopupPlacement,
+    /// window the result is sent to; None if not needed
+    owner: Option<WId>,
+    /// dialog request token, sent back with the result
+    token: DialogToken,
+    /// popup title
+    wnd_title: String,
+    /// text above the edit field
+    prompt: String,
+    /// allowed value range, if only the numbers are accepted
+    numeric: Option<(i64, i64)>,
+    // app-wide commands queue
+    cmds: Rc<RefCell<CommandsQueue>>,
+}
+
+impl InputDlgState {
+    pub fn new(wnd_id: WId, widgets: &'static [Widget], cmds: Rc<RefCell<CommandsQueue>>) -> Self {
+        InputDlgState {
+            wnd_id,
+            widgets,
+            rs: wgt::RuntimeStates::default(),
+            focused_id: idinp::EDIT_VALUE,
+            invalidated: Vec::with_capacity(4),
+            placement: PopupPlacement::new(&widgets[0]),
+            owner: None,
+            token: 0,
+            wnd_title: String::new(),
+            prompt: String::new(),
+            numeric: None,
+            cmds,
+        }
+    }
+
+    /// Setup the dialog before showing
+    ///
+    /// numeric: if set, only the integer numbers in given range are accepted;
+    /// the entered text is sent to the `owner` window, with the `token`
+    pub fn setup(
+        &mut self,
+        title: String,
+        prompt: String,
+        value: String,
+        numeric: Option<(i64, i64)>,
+        owner: Option<WId>,
+        token: DialogToken,
+    ) {
+        self.wnd_title = title;
+        if let Some(lbl) = wgt::find_by_id(self.widgets, idinp::LBL_PROMPT) {
+            self.prompt = utils::word_wrap(lbl.size.width as usize, &prompt)
+                .take()
+                .join("\n");
+        }
+        self.rs.txte.entry(idinp::EDIT_VALUE).or_default().txt = value;
+        self.numeric = numeric;
+        self.owner = owner;
+        self.token = token;
+    }
+
+    /// Returns the entered text, if it is valid
+    fn get_value(&mut self) -> Option<String> {
+        let txt = self.rs.txte.entry(idinp::EDIT_VALUE).or_default().txt.clone();
+
+        if let Some((min, max)) = self.numeric {
+            match txt.trim().parse::<i64>() {
+                Ok(n) if n >= min && n <= max => {}
+                _ => {
+                    tr_warn!("Value '{}' out of range {}..{}", txt, min, max);
+                    return None;
+                }
+            }
+        }
+
+        Some(txt)
+    }
+
+    /// Closes the popup, sending the result to the owner
+    fn close(&mut self, result: DialogResult) {
+        close_popup(&self.cmds, self.wnd_id, self.owner, self.token, result);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------
+
+impl AppWindow for InputDlgState {
+    fn on_screen_resize(&mut self, cols: u16, rows: u16) {
+        self.placement.on_screen_resize(cols, rows);
+    }
+}
+
+impl rtwins::wgt::WindowState for InputDlgState {
+    /** events **/
+
+    fn on_button_click(&mut self, wgt: &Widget, ii: &InputInfo) {
+        rtwins::tr_debug!("BTN_CLICK");
+
+        if wgt.id == idinp::BTN_OK {
+            match self.get_value() {
+                Some(txt) => self.close(DialogResult::Text(txt)),
+                None => {
+                    // let the user correct it
+                    self.focused_id = idinp::EDIT_VALUE;
+                    self.invalidate_many(&[idinp::EDIT_VALUE, idinp::BTN_OK]);
+                }
+            }
+        }
+        else if wgt.id == idinp::BTN_CANCEL {
+            self.close(DialogResult::Cancel);
+        }
+    }
+
+    fn on_text_edit_change(&mut self, wgt: &Widget, txt: &mut String) {
+        rtwins::tr_debug!("TXT_EDIT_CHANGE: {}", txt);
+        self.rs.txte.entry(wgt.id).or_default().txt = core::mem::take(txt);
+    }
+
+    fn on_text_edit_input_evt(
+        &mut self,
+        wgt: &Widget,
+        ii: &InputInfo,
+        txt: &mut String,
+        cursor_pos: &mut i16,
+    ) -> bool {
+        if let Some((min, max)) = self.numeric {
+            return rtwins::utils::num_edit_input_evt(ii, txt, cursor_pos, min, max, true);
+        }
+
+        // false means key not handled, continue with default code
+        false
+    }
+
+    fn on_window_unhandled_input_evt(&mut self, wgt: &Widget, ii: &InputInfo) -> bool {
+        rtwins::tr_debug!("on_window_unhandled_input_evt={}", ii.name);
+        if let InputEvent::Key(input::Key::Esc) = ii.evnt {
+            self.close(DialogResult::Closed);
+            return true;
+        }
+
+        false
+    }
+
+    /** common state queries **/
+
+    fn is_enabled(&self, wgt: &Widget) -> bool {
+        self.rs.get_enabled_or_default(wgt.id)
+    }
+
+    fn is_focused(&self, wgt: &Widget) -> bool {
+        self.focused_id == wgt.id
+    }
+
+    fn get_focused_id(&mut self) -> WId {
+        self.focused_id
+    }
+
+    fn set_focused_id(&mut self, wid: WId) {
+        self.focused_id = wid;
+    }
+
+    fn get_widgets(&self) -> &'static [Widget] {
+        self.widgets
+    }
+
+    fn get_rstate(&mut self) -> Option<&mut wgt::RuntimeStates> {
+        Some(&mut self.rs)
+    }
+
+    /** widget-specific queries; all mutable params are outputs **/
+
+    fn get_window_coord(&mut self) -> Coord {
+        self.placement.coord
+    }
+
+    fn get_window_size(&mut self) -> Size {
+        self.widgets.first().unwrap().size
+    }
+
+    fn get_window_title(&mut self, wgt: &Widget, out: &mut String) {
+        out.push_str(&self.wnd_title)
+    }
+
+    fn get_label_text(&mut self, wgt: &Widget, out: &mut String) {
+        if wgt.id == idinp::LBL_PROMPT {
+            out.push_str(&self.prompt);
+        }
+    }
+
+    fn get_text_edit_text(&mut self, wgt: &Widget, out: &mut String, edit_mode: bool) {
+        *out = self.rs.txte.entry(wgt.id).or_default().txt.clone();
+    }
+
+    /* requests */
+
+    fn invalidate_many(&mut self, wids: &[WId]) {
+        // iterate, to avoid adding the same ID twice
+        for wid in wids.iter() {
+            if !self.invalidated.contains(wid) {
+                self.invalidated.push(*wid);
+            }
+        }
+    }
+
+    fn instant_redraw(&mut self, wid: WId) {
+        if let Some(mut term_guard) = TERM.try_lock() {
+            term_guard.draw(self, &[wid]);
+            term_guard.flush_buff();
+        }
+        else {
+            rtwins::tr_warn!("Cannot lock the term");
+        }
+    }
+
+    fn clear_invalidated(&mut self) {
+        self.invalidated.clear();
+    }
+
+    fn get_invalidated(&mut self, out: &mut Vec<WId>) {
+        core::mem::swap(&mut self.invalidated, out);
+    }
+}
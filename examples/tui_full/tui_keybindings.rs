@@ -0,0 +1,300 @@
+//! # Global shortcuts
+//!
+//! Table mapping the keys to the application actions, dispatched by the main loop.
+//! Every action has the F-key binding, and the Ctrl+letter alternative for the consoles
+//! that cannot send the F-keys, like the QEMU semihosting one.
+//!
+//! The defaults can be changed with a config file, one action per line:
+//! `<action> = <key>[, <key>...]`, eg. `redraw = F5, C-l`; `#` starts a comment.
+//! The key is `F1`..`F12`, `PgUp`, `PgDown`, `Home`, `End`, `Esc` or a single letter,
+//! with optional `C-` prefix for the Ctrl.
+//! The main loop checks the bindings only for the keys not handled by the top window,
+//! so a plain letter binding does not fire while typing in a text edit
+
+use rtwins::input::{InputEvent, InputInfo, Key};
+
+use core::fmt;
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Application actions, that can be bound to the keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// list of the shortcuts
+    Help,
+    /// enable/disable the top window
+    ToggleWndEnabled,
+    /// output statistics in place of the main window footer
+    ToggleStats,
+    ToggleMouse,
+    /// clear the screen and draw all the windows
+    Redraw,
+    /// clear the logs area
+    TraceClear,
+    ToggleDrawDebug,
+    /// select the previous page of the main window
+    PagePrev,
+    /// select the next page of the main window
+    PageNext,
+    /// edit the number of hits, with the input dialog
+    EditHits,
+}
+
+/// Actions with their config file names and the descriptions
+pub const ACTIONS: [(Action, &str, &str); 10] = [
+    (Action::Help, "help", "This help"),
+    (Action::ToggleWndEnabled, "wnd_enable", "Enable/disable the window"),
+    (Action::ToggleStats, "stats", "Output statistics"),
+    (Action::ToggleMouse, "mouse", "Mouse on/off"),
+    (Action::Redraw, "redraw", "Redraw the screen"),
+    (Action::TraceClear, "trace_clear", "Clear the logs"),
+    (Action::ToggleDrawDebug, "draw_debug", "Draw debugging on/off"),
+    (Action::PagePrev, "page_prev", "Previous page"),
+    (Action::PageNext, "page_next", "Next page"),
+    (Action::EditHits, "edit_hits", "Edit the number of hits"),
+];
+
+/// Keys handled by the focused list box; described on its page and in the help
+pub const LISTBOX_KEYS: [(&str, &str); 3] = [
+    ("Up/Down", "change item"),
+    ("PgUp/PgDown", "scroll page"),
+    ("Enter", "select the item"),
+];
+
+/// Named keys accepted in the config file
+const KEY_NAMES: [(&str, Key); 17] = [
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+    ("PgUp", Key::PgUp),
+    ("PgDown", Key::PgDown),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("Esc", Key::Esc),
+];
+
+/// Key or the letter, that triggers the action
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    Key(Key),
+    /// lowercase letter
+    Char(char),
+}
+
+impl Trigger {
+    /// Returns the trigger of the decoded input event; the letters are case insensitive
+    pub fn from_input(ii: &InputInfo) -> Option<Self> {
+        match ii.evnt {
+            InputEvent::Key(ref key) => Some(Trigger::Key(*key)),
+            InputEvent::Char(ref ch) => ch
+                .as_str()
+                .chars()
+                .next()
+                .map(|c| Trigger::Char(c.to_ascii_lowercase())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub ctrl: bool,
+    pub action: Action,
+}
+
+impl Binding {
+    const fn key(key: Key, ctrl: bool, action: Action) -> Self {
+        Binding {
+            trigger: Trigger::Key(key),
+            ctrl,
+            action,
+        }
+    }
+
+    const fn ctrl_char(c: char, action: Action) -> Self {
+        Binding {
+            trigger: Trigger::Char(c),
+            ctrl: true,
+            action,
+        }
+    }
+
+    /// Parses the config file key, like `F5`, `C-PgUp` or `C-l`
+    fn parse(s: &str, action: Action) -> Option<Self> {
+        let (ctrl, name) = match s.strip_prefix("C-") {
+            Some(name) => (true, name),
+            None => (false, s),
+        };
+
+        let trigger = match KEY_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some((_, key)) => Trigger::Key(*key),
+            None => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphabetic() => {
+                        Trigger::Char(c.to_ascii_lowercase())
+                    }
+                    _ => return None,
+                }
+            }
+        };
+
+        Some(Binding {
+            trigger,
+            ctrl,
+            action,
+        })
+    }
+}
+
+// Ctrl+letters already taken by the terminal and the input sources:
+// C, D (quit), H, I, J, M, Q, S, Z
+#[rustfmt::skip]
+const DEFAULT_BINDINGS: [Binding; 22] = [
+    Binding::key(Key::F1, false, Action::Help),
+    Binding::ctrl_char('u', Action::Help),
+    Binding::key(Key::F2, false, Action::ToggleWndEnabled),
+    Binding::ctrl_char('e', Action::ToggleWndEnabled),
+    Binding::key(Key::F3, false, Action::ToggleStats),
+    Binding::ctrl_char('t', Action::ToggleStats),
+    Binding::key(Key::F4, false, Action::ToggleMouse),
+    Binding::ctrl_char('o', Action::ToggleMouse),
+    Binding::key(Key::F5, false, Action::Redraw),
+    Binding::ctrl_char('l', Action::Redraw),
+    Binding::key(Key::F6, false, Action::TraceClear),
+    Binding::ctrl_char('k', Action::TraceClear),
+    Binding::key(Key::F7, false, Action::ToggleDrawDebug),
+    Binding::ctrl_char('g', Action::ToggleDrawDebug),
+    Binding::key(Key::F9, false, Action::PagePrev),
+    Binding::key(Key::PgUp, true, Action::PagePrev),
+    Binding::ctrl_char('p', Action::PagePrev),
+    Binding::key(Key::F10, false, Action::PageNext),
+    Binding::key(Key::PgDown, true, Action::PageNext),
+    Binding::ctrl_char('n', Action::PageNext),
+    Binding::key(Key::F11, false, Action::EditHits),
+    Binding::ctrl_char('y', Action::EditHits),
+];
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+
+        match self.trigger {
+            Trigger::Key(key) => {
+                let name = KEY_NAMES.iter().find(|(_, k)| *k == key).map_or("?", |(n, _)| n);
+                f.write_str(name)
+            }
+            Trigger::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct KeyBindings {
+    bindings: Vec<Binding>,
+}
+
+impl KeyBindings {
+    /// Creates the table with the default bindings
+    pub fn new() -> Self {
+        KeyBindings {
+            bindings: Vec::from(DEFAULT_BINDINGS),
+        }
+    }
+
+    /// Returns the action bound to the input event
+    pub fn find(&self, ii: &InputInfo) -> Option<Action> {
+        let ctrl = ii.kmod.has_ctrl();
+        Trigger::from_input(ii).and_then(|trigger| self.find_trigger(trigger, ctrl))
+    }
+
+    /// Returns the action bound to the key; the Ctrl state must match
+    pub fn find_trigger(&self, trigger: Trigger, ctrl: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|b| b.trigger == trigger && b.ctrl == ctrl)
+            .map(|b| b.action)
+    }
+
+    /// Iterates over the bindings, in the table order
+    pub fn iter(&self) -> impl Iterator<Item = &Binding> + '_ {
+        self.bindings.iter()
+    }
+
+    /// Returns the keys, like `F5, Ctrl+L`, with the description of every bound action
+    pub fn describe(&self) -> Vec<(String, &'static str)> {
+        let mut out = Vec::with_capacity(ACTIONS.len());
+
+        for (action, _, descr) in ACTIONS.iter() {
+            let keys: Vec<String> = self
+                .iter()
+                .filter(|b| b.action == *action)
+                .map(|b| format!("{}", b))
+                .collect();
+
+            if !keys.is_empty() {
+                out.push((keys.join(", "), *descr));
+            }
+        }
+
+        out
+    }
+
+    /// Applies the config file; the keys of the listed actions replace the default ones.
+    /// On error, the bindings are not changed
+    pub fn load(&mut self, config: &str) -> Result<(), String> {
+        let mut bindings = self.bindings.clone();
+
+        for (nr, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, keys) = match line.split_once('=') {
+                Some((name, keys)) => (name.trim(), keys),
+                None => return Err(format!("line {}: expected '<action> = <keys>'", nr + 1)),
+            };
+
+            let action = match ACTIONS.iter().find(|(_, n, _)| *n == name) {
+                Some((action, _, _)) => *action,
+                None => return Err(format!("line {}: unknown action '{}'", nr + 1, name)),
+            };
+
+            bindings.retain(|b| b.action != action);
+
+            for key in keys.split(',').map(|k| k.trim()).filter(|k| !k.is_empty()) {
+                match Binding::parse(key, action) {
+                    Some(binding) => {
+                        // the key triggers only one action
+                        bindings.retain(|b| b.trigger != binding.trigger || b.ctrl != binding.ctrl);
+                        bindings.push(binding);
+                    }
+                    None => return Err(format!("line {}: unknown key '{}'", nr + 1, key)),
+                }
+            }
+        }
+
+        self.bindings = bindings;
+        Ok(())
+    }
+}
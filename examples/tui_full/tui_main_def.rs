@@ -81,6 +81,7 @@ pub mod id {
                     PANEL_EMPTY_2
                         LBL_EMPTY2
             LABEL_FTR
+            LABEL_STATS
     );
 }
 
@@ -129,7 +130,7 @@ const PAGE_PANELS_CHILDREN: &[Widget] = &[
         id: id::PANEL_VERSIONS,
         link: Link::cdeflt(),
         coord: Coord { col: 1, row: 1 },
-        size: Size { width: 21, height: 5 },
+        size: Size { width: 26, height: 5 },
         prop: prop::Panel {
             title: "VER 🍁",
             fg_color: ColorFg::White,
@@ -150,7 +151,7 @@ const PAGE_PANELS_CHILDREN: &[Widget] = &[
             Widget {
                 id: id::LABEL_DATE,
                 coord: Coord { col: 2, row: 2 },
-                size: Size { width: 18, height: 1 },
+                size: Size { width: 22, height: 1 },
                 prop: prop::Label {
                     title: "",
                     fg_color: ColorFg::Black,
@@ -845,6 +846,17 @@ const WINDOW_MAIN: Widget = Widget {
             }.into(),
             ..Widget::cdeflt()
         },
+        Widget {
+            id: id::LABEL_STATS,
+            coord: Coord { col: 1, row: 13 },
+            size: Size { width: 78, height: 1 },
+            prop: prop::Label {
+                title:  "",
+                fg_color: ColorFg::White,
+                bg_color: ColorBgTheme::LabelFtr.into(),
+            }.into(),
+            ..Widget::cdeflt()
+        },
     ]
 };
 
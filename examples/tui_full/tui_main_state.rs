@@ -8,14 +8,18 @@ use rtwins::esc;
 use rtwins::input::*;
 use rtwins::string_ext::StringExt;
 use rtwins::utils;
-use rtwins::wgt::{self, rstate, WId, Widget, WIDGET_ID_NONE};
+use rtwins::wgt::{self, rstate, WId, Widget, WindowState, WIDGET_ID_NONE};
 use rtwins::Term;
 use rtwins::TERM;
 use rtwins::*;
 
+use super::clock;
 use super::tui_commands::*;
+use super::tui_keybindings::LISTBOX_KEYS;
 use super::tui_main_def::id;
-use crate::tui_msgbox_def::idmb;
+use super::tui_persist::{self, BlobWriter, PersistError};
+use super::tui_timers::TimerToken;
+use super::tui_wnd_mngr::AppWindow;
 
 use core::cell::RefCell;
 
@@ -30,6 +34,21 @@ use alloc::{format, vec};
 
 // ---------------------------------------------------------------------------------------------- //
 
+/// Dialogs requested by the main window
+pub const DLG_LOREM: DialogToken = 1;
+pub const DLG_CONFIRM_NO: DialogToken = 2;
+pub const DLG_HITS: DialogToken = 3;
+
+pub const TMR_PRGBARS: TimerToken = 1;
+pub const TMR_LED_PUMP: TimerToken = 2;
+/// LABEL_DATE refresh
+pub const TMR_CLOCK: TimerToken = 3;
+
+// window own sections of the persisted state
+const SECTION_RADIO: u8 = tui_persist::SECTION_APP;
+const SECTION_FOCUS: u8 = tui_persist::SECTION_APP + 1;
+const SECTION_HITS: u8 = tui_persist::SECTION_APP + 2;
+
 /// State of all the DemoWindow widget dynamic properties
 pub struct MainWndState {
     // id of the window
@@ -50,6 +69,14 @@ pub struct MainWndState {
     tbx_text: String,
     tbx_wide_lines: utils::StringListRc,
     tbx_narrow_lines: utils::StringListRc,
+    /// text edit in the edit mode
+    edited_id: Option<WId>,
+    /// number of hits, set with the input dialog
+    hits: String,
+    /// output statistics shown in place of the footer
+    pub show_stats: bool,
+    /// number of the toasts requested; selects the next one severity
+    toasts_count: u16,
     // app-wide commands queue
     cmds: Rc<RefCell<CommandsQueue>>,
 }
@@ -67,6 +94,10 @@ impl MainWndState {
             tbx_text: String::with_capacity(400),
             tbx_wide_lines: Arc::new(RefCell::new(vec![])),
             tbx_narrow_lines: Arc::new(RefCell::new(vec![])),
+            edited_id: None,
+            hits: "20".to_owned(),
+            show_stats: false,
+            toasts_count: 0,
             cmds,
         };
 
@@ -162,12 +193,183 @@ impl MainWndState {
             },
         );
 
+        // animations
+        match wnd_state.cmds.try_borrow_mut() {
+            Ok(ref mut cmds) => {
+                cmds.push(Command::StartTimer {
+                    owner: wnd_id,
+                    token: TMR_PRGBARS,
+                    interval_ms: 300,
+                    periodic: true,
+                });
+                cmds.push(Command::StartTimer {
+                    owner: wnd_id,
+                    token: TMR_LED_PUMP,
+                    interval_ms: 500,
+                    periodic: true,
+                });
+                cmds.push(Command::StartTimer {
+                    owner: wnd_id,
+                    token: TMR_CLOCK,
+                    interval_ms: 1000,
+                    periodic: true,
+                });
+            }
+            Err(e) => tr_err!("Cannot borrow the commands"),
+        }
+
         wnd_state
     }
+
+    /// Serializes the widgets state and the window fields
+    pub fn save_state(&self) -> Result<Vec<u8>, PersistError> {
+        let mut w = BlobWriter::new(tui_persist::fingerprint(self.widgets));
+        tui_persist::write_rstate(&mut w, &self.rs);
+        w.section(SECTION_RADIO, |w| w.put_i16(self.radiogrp1_idx));
+        w.section(SECTION_FOCUS, |w| {
+            self.focused_ids.iter().for_each(|wid| w.put_u16(*wid as u16));
+        });
+        w.section(SECTION_HITS, |w| w.put_str(&self.hits));
+        w.finish()
+    }
+
+    /// Restores the state saved with `save_state()`; on error, nothing is changed
+    pub fn restore_state(&mut self, blob: &[u8]) -> Result<(), PersistError> {
+        let sections = tui_persist::open_blob(blob, tui_persist::fingerprint(self.widgets))?;
+
+        for (tag, mut rd) in sections.into_iter() {
+            match tag {
+                SECTION_RADIO => {
+                    if let Some(idx) = rd.get_i16() {
+                        self.radiogrp1_idx = idx;
+                    }
+                }
+                SECTION_FOCUS => {
+                    for focused_id in self.focused_ids.iter_mut() {
+                        match rd.get_u16() {
+                            Some(wid) => *focused_id = wid as WId,
+                            None => break,
+                        }
+                    }
+                }
+                SECTION_HITS => {
+                    if let Some(hits) = rd.get_str() {
+                        self.hits = hits;
+                    }
+                }
+                _ => tui_persist::read_rstate(tag, &mut rd, &mut self.rs),
+            }
+        }
+
+        // the focused ids are indexed with the page
+        let pages = self.focused_ids.len() as i16;
+        let pgctrl = self.rs.pgctrl.entry(id::PG_CONTROL).or_default();
+        if pgctrl.page < 0 || pgctrl.page >= pages {
+            pgctrl.page = 0;
+        }
+
+        self.invalidate(wgt::WIDGET_ID_ALL);
+        Ok(())
+    }
+
+    /// Opens the input dialog, editing the number of hits
+    pub fn edit_hits(&mut self) {
+        match self.cmds.try_borrow_mut() {
+            Ok(ref mut cmds) => cmds.push(Command::ShowInputDialog {
+                title: "Hits".to_owned(),
+                prompt: "Number of hits, 1..99:".to_owned(),
+                value: self.hits.clone(),
+                numeric: Some((1, 99)),
+                owner: Some(self.wnd_id),
+                token: DLG_HITS,
+            }),
+            Err(e) => tr_err!("Cannot borrow the commands"),
+        }
+    }
+
+    /// Inserts the pasted text into the focused text edit
+    pub fn paste(&mut self, text: &str) {
+        let focused_id = self.get_focused_id();
+
+        if !matches!(focused_id, id::EDIT1 | id::EDIT_PSW) {
+            rtwins::tr_warn!("Paste ignored: text edit not focused");
+            return;
+        }
+
+        // single line edits: no line breaks, tabs...
+        let text: String = text
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+
+        if self.edited_id != Some(focused_id) {
+            // not in the edit mode: appended to the edit text
+            self.rs.txte.entry(focused_id).or_default().txt.push_str(&text);
+            self.invalidate(focused_id);
+            return;
+        }
+
+        // in the edit mode, the edited text is kept by the widget;
+        // delivered as the typed characters, so the edit inserts them at its cursor
+        let mut ii = InputInfo::default();
+        let mut ique = rtwins::input_decoder::InputQue::new();
+        let mut dec = rtwins::input_decoder::Decoder::default();
+        ique.extend(text.as_bytes().iter());
+
+        while dec.decode_input_seq(&mut ique, &mut ii) > 0 {
+            wgt::process_input(self, &ii);
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------------------------
 
+impl AppWindow for MainWndState {
+    fn on_dialog_result(&mut self, token: DialogToken, result: DialogResult) {
+        tr_info!("{}Dialog {} result: {:?}{}", esc::BG_DARK_CYAN, token, result, esc::BG_DEFAULT);
+
+        if let (DLG_HITS, DialogResult::Text(hits)) = (token, &result) {
+            self.hits = hits.trim().to_owned();
+            self.invalidate(id::LBL_WORDWRAP);
+        }
+
+        if token == DLG_LOREM && result == DialogResult::No {
+            // nested popup
+            match self.cmds.try_borrow_mut() {
+                Ok(ref mut cmds) => cmds.push(Command::ShowPopup {
+                    title: "Confirm".to_owned(),
+                    message: "Are you sure it's NO?".to_owned(),
+                    buttons: "yn",
+                    owner: Some(self.wnd_id),
+                    token: DLG_CONFIRM_NO,
+                }),
+                Err(e) => tr_err!("Cannot borrow the commands"),
+            }
+        }
+    }
+
+    fn on_timer(&mut self, token: TimerToken) {
+        if token == TMR_PRGBARS {
+            let prgbars = [id::PRGBAR1, id::PRGBAR2, id::PRGBAR3];
+
+            for wid in prgbars.iter() {
+                let rs = self.rs.pgbar.entry(*wid).or_default();
+                rs.pos = if rs.pos >= rs.max { 0 } else { rs.pos + 1 };
+            }
+
+            self.invalidate_many(&prgbars);
+        }
+        else if token == TMR_LED_PUMP {
+            let rs = self.rs.led.entry(id::LED_PUMP).or_default();
+            rs.lit = !rs.lit;
+            self.invalidate(id::LED_PUMP);
+        }
+        else if token == TMR_CLOCK {
+            self.invalidate(id::LABEL_DATE);
+        }
+    }
+}
+
 impl rtwins::wgt::WindowState for MainWndState {
     /** events **/
 
@@ -219,15 +421,8 @@ impl rtwins::wgt::WindowState for MainWndState {
                                 Ut enim ad minim veniam, quis nostrud exercitation ullamco \
                                 laboris nisi ut aliquip ex ea commodo consequat.".to_owned(),
                             buttons: "ync",
-                            on_button: Box::new(move |btn_id| {
-                                let msg = match btn_id {
-                                    idmb::BTN_YES => "YES",
-                                    idmb::BTN_NO => "NO",
-                                    idmb::BTN_CANCEL => "CANCEL",
-                                    _ => ""
-                                };
-                                tr_info!("{}MsgBox callback: {msg}{}", esc::BG_DARK_CYAN, esc::BG_DEFAULT);
-                            }),
+                            owner: Some(self.wnd_id),
+                            token: DLG_LOREM,
                         }
                     );
                 }
@@ -240,6 +435,23 @@ impl rtwins::wgt::WindowState for MainWndState {
             self.invalidate(id::PANEL_EDT);
         }
 
+        if wgt.id == id::BTN_TOASTER {
+            self.toasts_count += 1;
+            let severity = match self.toasts_count % 3 {
+                1 => Severity::Info,
+                2 => Severity::Warning,
+                _ => Severity::Error,
+            };
+
+            match self.cmds.try_borrow_mut() {
+                Ok(ref mut cmds) => cmds.push(Command::ShowToast {
+                    severity,
+                    message: format!("Toast #{}: {:?}", self.toasts_count, severity),
+                }),
+                Err(e) => tr_err!("Cannot borrow the commands"),
+            }
+        }
+
         if wgt.id == id::BTN_YES {
             wgt::pagectrl_select_page(self, id::PG_CONTROL, id::PAGE_TEXTBOX);
         }
@@ -273,6 +485,7 @@ impl rtwins::wgt::WindowState for MainWndState {
 
     fn on_text_edit_change(&mut self, wgt: &Widget, txt: &mut String) {
         rtwins::tr_debug!("TXT_EDIT_CHANGE: {}", txt);
+        self.edited_id = None;
         self.rs.txte.entry(wgt.id).or_default().txt = core::mem::take(txt);
     }
 
@@ -283,6 +496,12 @@ impl rtwins::wgt::WindowState for MainWndState {
         txt: &mut String,
         cursor_pos: &mut i16,
     ) -> bool {
+        // called only in the edit mode, that the Esc cancels
+        self.edited_id = match ii.evnt {
+            InputEvent::Key(Key::Esc) => None,
+            _ => Some(wgt.id),
+        };
+
         if wgt.id == id::EDIT2 {
             return rtwins::utils::num_edit_input_evt(
                 ii,
@@ -427,6 +646,14 @@ impl rtwins::wgt::WindowState for MainWndState {
             return self.rs.chbx.get(&id::CHBX_L2).map_or(true, |rs| rs.checked);
         }
 
+        if wgt.id == id::LABEL_FTR {
+            return !self.show_stats;
+        }
+
+        if wgt.id == id::LABEL_STATS {
+            return self.show_stats;
+        }
+
         true
     }
 
@@ -479,7 +706,10 @@ impl rtwins::wgt::WindowState for MainWndState {
 
     fn get_label_text(&mut self, wgt: &Widget, out: &mut String) {
         if wgt.id == id::LABEL_DATE {
-            out.push_str(format!("Date•{}", "<datetime>").as_str());
+            out.push_str(format!("Date•{}", clock::now().date_time_str()).as_str());
+        }
+        else if wgt.id == id::LABEL_STATS {
+            out.push_str(&crate::pal_stats::summary());
         }
         else if wgt.id == id::LABEL_ABOUT {
             out.push_str(rtwins::url_link!(
@@ -493,22 +723,17 @@ impl rtwins::wgt::WindowState for MainWndState {
                 << esc::INVERSE_ON
                 << "ListBox manual:"
                 << esc::INVERSE_OFF
-                << " ▫▫▫▫▫\n"
-                << "• "
-                << esc::UNDERLINE_ON
-                << "Up/Down"
-                << esc::UNDERLINE_OFF
-                << " -> change item\n"
-                << "• "
-                << esc::UNDERLINE_ON
-                << "PgUp/PgDown"
-                << esc::UNDERLINE_OFF
-                << " -> scroll page\n"
-                << "• "
-                << esc::UNDERLINE_ON
-                << "Enter"
-                << esc::UNDERLINE_OFF
-                << " -> select the item";
+                << " ▫▫▫▫▫";
+
+            for (keys, descr) in LISTBOX_KEYS.iter() {
+                let _ = out.stream()
+                    << "\n• "
+                    << esc::UNDERLINE_ON
+                    << *keys
+                    << esc::UNDERLINE_OFF
+                    << " -> "
+                    << *descr;
+            }
         }
         else if wgt.id == id::LBL_WORDWRAP {
             let mut tmp = String::with_capacity(100);
@@ -516,7 +741,9 @@ impl rtwins::wgt::WindowState for MainWndState {
                 << esc::BOLD
                 << "Name:\n"
                 << esc::NORMAL
-                << "  20 Hits on 2\n"
+                << "  "
+                << self.hits.as_str()
+                << " Hits on 2\n"
                 << esc::BOLD
                 << "Description:\n"
                 << esc::NORMAL
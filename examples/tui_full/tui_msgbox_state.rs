@@ -13,6 +13,7 @@ use rtwins::*;
 
 use super::tui_commands::*;
 use super::tui_msgbox_def::idmb;
+use super::tui_wnd_mngr::{close_popup, AppWindow, PopupPlacement};
 
 use core::cell::RefCell;
 
@@ -37,10 +38,12 @@ pub struct MsgBoxState {
     focused_id: WId,
     /// list of widgets to redraw
     invalidated: Vec<WId>,
-    // popup coordinates, centered over main window
-    coord: Coord,
-    /// button click handler
-    on_button: Box<dyn Fn(WId) + Send>,
+    /// popup location, centered over main window
+    pub placement: PopupPlacement,
+    /// window the result is sent to; None if not needed
+    owner: Option<WId>,
+    /// dialog request token, sent back with the result
+    token: DialogToken,
     /// popup title
     wnd_title: String,
     /// popup message
@@ -59,8 +62,9 @@ impl MsgBoxState {
             rs: wgt::RuntimeStates::default(),
             focused_id: WIDGET_ID_NONE,
             invalidated: Vec::with_capacity(4),
-            coord: Coord::cdeflt(),
-            on_button: Box::new(|_id: WId| {}),
+            placement: PopupPlacement::new(&widgets[0]),
+            owner: None,
+            token: 0,
             wnd_title: String::new(),
             wnd_message: String::new(),
             buttons: "ynoc",
@@ -68,24 +72,17 @@ impl MsgBoxState {
         }
     }
 
-    pub fn center_on(&mut self, wnd: &Widget) {
-        let wndpopup = &self.widgets[0];
-        // calc location on the main window center
-        self.coord.col = (wnd.size.width - wndpopup.size.width) / 2;
-        self.coord.col += wnd.coord.col;
-        self.coord.row = (wnd.size.height - wndpopup.size.height) / 2;
-        self.coord.row += wnd.coord.row;
-    }
-
     /// Setup the MessageBox befor showing
     ///
-    /// buttons: string of 'ynoc' defining visibility of Yes/No/Ok/Cancel buttons
+    /// buttons: string of 'ynoc' defining visibility of Yes/No/Ok/Cancel buttons;
+    /// the result is sent to the `owner` window, with the `token`
     pub fn setup(
         &mut self,
         title: String,
         message: String,
         buttons: &'static str,
-        on_button: Box<dyn Fn(WId) + Send>,
+        owner: Option<WId>,
+        token: DialogToken,
     ) {
         self.wnd_title = title;
         if let Some(lbl) = wgt::find_by_id(self.widgets, idmb::LBL_MSG) {
@@ -94,23 +91,37 @@ impl MsgBoxState {
                 .join("\n");
         }
         self.buttons = buttons;
-        self.on_button = on_button;
+        self.owner = owner;
+        self.token = token;
+    }
+
+    /// Closes the popup, sending the result to the owner
+    fn close(&mut self, result: DialogResult) {
+        close_popup(&self.cmds, self.wnd_id, self.owner, self.token, result);
     }
 }
 
 // -----------------------------------------------------------------------------------------------
 
+impl AppWindow for MsgBoxState {
+    fn on_screen_resize(&mut self, cols: u16, rows: u16) {
+        self.placement.on_screen_resize(cols, rows);
+    }
+}
+
 impl rtwins::wgt::WindowState for MsgBoxState {
     /** events **/
 
     fn on_button_click(&mut self, wgt: &Widget, ii: &InputInfo) {
         rtwins::tr_debug!("BTN_CLICK");
-        self.on_button.as_ref()(wgt.id);
 
-        match self.cmds.try_borrow_mut() {
-            Ok(ref mut cmds) => cmds.push(Command::HidePopup),
-            Err(e) => tr_err!("Cannot borrow commands"),
-        }
+        let result = match wgt.id {
+            idmb::BTN_YES => DialogResult::Yes,
+            idmb::BTN_NO => DialogResult::No,
+            idmb::BTN_OK => DialogResult::Ok,
+            _ => DialogResult::Cancel,
+        };
+        self.close(result);
     }
 
     fn on_button_key(&mut self, wgt: &Widget, ii: &InputInfo) -> bool {
@@ -120,9 +131,7 @@ impl rtwins::wgt::WindowState for MsgBoxState {
     fn on_window_unhandled_input_evt(&mut self, wgt: &Widget, ii: &InputInfo) -> bool {
         rtwins::tr_debug!("on_window_unhandled_input_evt={}", ii.name);
         if let InputEvent::Key(input::Key::Esc) = ii.evnt {
-            if let Ok(ref mut cmds) = self.cmds.try_borrow_mut() {
-                cmds.push(Command::HidePopup);
-            }
+            self.close(DialogResult::Closed);
             return true;
         }
 
@@ -169,7 +178,7 @@ impl rtwins::wgt::WindowState for MsgBoxState {
     /** widget-specific queries; all mutable params are outputs **/
 
     fn get_window_coord(&mut self) -> Coord {
-        self.coord
+        self.placement.coord
     }
 
     fn get_window_size(&mut self) -> Size {
@@ -0,0 +1,382 @@
+//! # Window state persistence
+//!
+//! The state is stored as a compact binary blob, all numbers little-endian:
+//! `"RTWS" | version: u8 | fingerprint: u16 | sections... | checksum: u16`,
+//! where each section is `tag: u8 | length: u16 | payload`.
+//!
+//! Schema changes:
+//! - unknown sections are skipped, so the new data is added as the new section;
+//!   the section tag is never reused with a different payload layout
+//! - the blob of a different widgets tree is dropped, as the widget ids are no longer valid;
+//!   the tree is identified with the fingerprint
+//! - the blob of a different version is dropped; the version changes with the header layout
+
+use rtwins::wgt::{self, WId, Widget};
+
+use crate::host_file::HostFile;
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+const MAGIC: &[u8; 4] = b"RTWS";
+const VERSION: u8 = 1;
+/// magic, version, fingerprint
+const HEADER_LEN: usize = 7;
+
+// RuntimeStates sections; the window sections use the tags from `SECTION_APP`
+const SECTION_CHBX: u8 = 1;
+const SECTION_TXTE: u8 = 2;
+const SECTION_LBX: u8 = 3;
+const SECTION_CBBX: u8 = 4;
+const SECTION_PGCTRL: u8 = 5;
+const SECTION_TXTBX: u8 = 6;
+/// First tag available for the window own sections
+pub const SECTION_APP: u8 = 0x80;
+
+#[derive(Debug, PartialEq)]
+pub enum PersistError {
+    BadMagic,
+    Version(u8),
+    /// blob saved for a different widgets tree
+    Fingerprint(u16),
+    Checksum,
+    Truncated,
+    /// the storage cannot be written
+    Write,
+    /// blob larger than the storage, or the section or text longer than 64kB
+    TooLarge,
+}
+
+/// Fletcher-16
+fn checksum(data: &[u8]) -> u16 {
+    let (mut s1, mut s2) = (0u16, 0u16);
+
+    for b in data.iter() {
+        s1 = (s1 + *b as u16) % 255;
+        s2 = (s2 + s1) % 255;
+    }
+
+    (s2 << 8) | s1
+}
+
+/// Identifies the widgets tree: the ids and the types of all the widgets
+pub fn fingerprint(widgets: &[Widget]) -> u16 {
+    let mut data = Vec::with_capacity(widgets.len() * 8);
+
+    for w in widgets.iter() {
+        data.extend_from_slice(&(w.id as u16).to_le_bytes());
+        data.extend_from_slice(format!("{}", w.prop).as_bytes());
+    }
+
+    checksum(&data)
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct BlobWriter {
+    buf: Vec<u8>,
+    /// set if some length did not fit in the u16; then `finish()` fails
+    too_large: bool,
+}
+
+impl BlobWriter {
+    pub fn new(fingerprint: u16) -> Self {
+        let mut buf = Vec::with_capacity(256);
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        buf.extend_from_slice(&fingerprint.to_le_bytes());
+        BlobWriter {
+            buf,
+            too_large: false,
+        }
+    }
+
+    /// Writes the section, with the payload written by `f`
+    pub fn section<F: FnOnce(&mut Self)>(&mut self, tag: u8, f: F) {
+        self.buf.push(tag);
+        let len_at = self.buf.len();
+        self.put_u16(0);
+
+        f(self);
+
+        match u16::try_from(self.buf.len() - len_at - 2) {
+            Ok(len) => self.buf[len_at..len_at + 2].copy_from_slice(&len.to_le_bytes()),
+            Err(_) => self.too_large = true,
+        }
+    }
+
+    pub fn put_u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    pub fn put_u16(&mut self, val: u16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn put_i16(&mut self, val: i16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn put_str(&mut self, val: &str) {
+        match u16::try_from(val.len()) {
+            Ok(len) => {
+                self.put_u16(len);
+                self.buf.extend_from_slice(val.as_bytes());
+            }
+            Err(_) => self.too_large = true,
+        }
+    }
+
+    /// Returns the blob, closed with the checksum
+    pub fn finish(mut self) -> Result<Vec<u8>, PersistError> {
+        if self.too_large {
+            return Err(PersistError::TooLarge);
+        }
+
+        let cs = checksum(&self.buf);
+        self.put_u16(cs);
+        Ok(self.buf)
+    }
+}
+
+/// Reads the section payload; the getters return None past the end
+pub struct BlobReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BlobReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.data.len() < n {
+            return None;
+        }
+
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Some(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn get_u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    pub fn get_u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn get_i16(&mut self) -> Option<i16> {
+        self.take(2).map(|b| i16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn get_str(&mut self) -> Option<String> {
+        let len = self.get_u16()? as usize;
+        let bytes = self.take(len)?;
+        core::str::from_utf8(bytes).ok().map(String::from)
+    }
+}
+
+/// Validates the blob; returns the sections
+pub fn open_blob(
+    blob: &[u8],
+    fingerprint: u16,
+) -> Result<Vec<(u8, BlobReader<'_>)>, PersistError> {
+    if blob.len() < MAGIC.len() || &blob[..MAGIC.len()] != MAGIC {
+        return Err(PersistError::BadMagic);
+    }
+
+    if blob.len() < HEADER_LEN + 2 {
+        return Err(PersistError::Truncated);
+    }
+
+    let (body, cs) = blob.split_at(blob.len() - 2);
+    if checksum(body) != u16::from_le_bytes([cs[0], cs[1]]) {
+        return Err(PersistError::Checksum);
+    }
+
+    if body[4] != VERSION {
+        return Err(PersistError::Version(body[4]));
+    }
+
+    let blob_fp = u16::from_le_bytes([body[5], body[6]]);
+    if blob_fp != fingerprint {
+        return Err(PersistError::Fingerprint(blob_fp));
+    }
+
+    let mut rd = BlobReader {
+        data: &body[HEADER_LEN..],
+    };
+    let mut sections = Vec::new();
+
+    while !rd.is_empty() {
+        let tag = rd.get_u8().ok_or(PersistError::Truncated)?;
+        let len = rd.get_u16().ok_or(PersistError::Truncated)? as usize;
+        let data = rd.take(len).ok_or(PersistError::Truncated)?;
+        sections.push((tag, BlobReader { data }));
+    }
+
+    Ok(sections)
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Writes the widgets state that is changed by the user
+pub fn write_rstate(w: &mut BlobWriter, rs: &wgt::RuntimeStates) {
+    w.section(SECTION_CHBX, |w| {
+        for (id, st) in rs.chbx.iter() {
+            w.put_u16(*id as u16);
+            w.put_u8(st.checked as u8);
+        }
+    });
+
+    w.section(SECTION_TXTE, |w| {
+        for (id, st) in rs.txte.iter() {
+            w.put_u16(*id as u16);
+            w.put_str(&st.txt);
+        }
+    });
+
+    w.section(SECTION_LBX, |w| {
+        for (id, st) in rs.lbx.iter() {
+            w.put_u16(*id as u16);
+            w.put_i16(st.sel_idx);
+            w.put_i16(st.item_idx);
+        }
+    });
+
+    w.section(SECTION_CBBX, |w| {
+        for (id, st) in rs.cbbx.iter() {
+            w.put_u16(*id as u16);
+            w.put_i16(st.sel_idx);
+            w.put_i16(st.item_idx);
+        }
+    });
+
+    w.section(SECTION_PGCTRL, |w| {
+        for (id, st) in rs.pgctrl.iter() {
+            w.put_u16(*id as u16);
+            w.put_i16(st.page);
+        }
+    });
+
+    w.section(SECTION_TXTBX, |w| {
+        for (id, st) in rs.txtbx.iter() {
+            w.put_u16(*id as u16);
+            w.put_i16(st.top_line);
+        }
+    });
+}
+
+/// Restores the widgets state from the section written by `write_rstate()`;
+/// other sections are ignored
+pub fn read_rstate(tag: u8, rd: &mut BlobReader, rs: &mut wgt::RuntimeStates) {
+    while !rd.is_empty() {
+        let id = match rd.get_u16() {
+            Some(id) => id as WId,
+            None => return,
+        };
+
+        let restored = match tag {
+            SECTION_CHBX => rd
+                .get_u8()
+                .map(|checked| rs.chbx.entry(id).or_default().checked = checked != 0),
+            SECTION_TXTE => rd
+                .get_str()
+                .map(|txt| rs.txte.entry(id).or_default().txt = txt),
+            SECTION_LBX => rd.get_i16().zip(rd.get_i16()).map(|(sel_idx, item_idx)| {
+                let st = rs.lbx.entry(id).or_default();
+                st.sel_idx = sel_idx;
+                st.item_idx = item_idx;
+            }),
+            SECTION_CBBX => rd.get_i16().zip(rd.get_i16()).map(|(sel_idx, item_idx)| {
+                let st = rs.cbbx.entry(id).or_default();
+                st.sel_idx = sel_idx;
+                st.item_idx = item_idx;
+            }),
+            SECTION_PGCTRL => rd
+                .get_i16()
+                .map(|page| rs.pgctrl.entry(id).or_default().page = page),
+            SECTION_TXTBX => rd
+                .get_i16()
+                .map(|top_line| rs.txtbx.entry(id).or_default().top_line = top_line),
+            _ => None,
+        };
+
+        if restored.is_none() {
+            return;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Maximum blob size kept in the RAM region
+#[cfg(all(target_os = "none", feature = "persist_ram"))]
+const PERSIST_RAM_SIZE: usize = 1024;
+
+/// RAM region reserved in the `memory.x`, not initialized by the runtime,
+/// so the content survives the reset; the blob is prefixed with its length
+#[cfg(all(target_os = "none", feature = "persist_ram"))]
+#[link_section = ".persist"]
+static mut PERSIST_RAM: core::mem::MaybeUninit<[u8; PERSIST_RAM_SIZE]> =
+    core::mem::MaybeUninit::uninit();
+
+/// Where the state blob is kept
+pub enum Storage {
+    /// file on the host filesystem; with semihosting on target
+    File(String),
+    /// RAM region kept over the reset
+    #[cfg(all(target_os = "none", feature = "persist_ram"))]
+    Ram,
+}
+
+impl Storage {
+    pub fn load(&self) -> Option<Vec<u8>> {
+        match self {
+            Storage::File(path) => HostFile::read_all(path),
+            #[cfg(all(target_os = "none", feature = "persist_ram"))]
+            Storage::Ram => {
+                // the region is not initialized after the power-on, so it is read byte by byte,
+                // as the plain values; the garbage is rejected by the blob checksum
+                let ram = unsafe { core::ptr::addr_of!(PERSIST_RAM).cast::<u8>() };
+                let read = |idx: usize| unsafe { core::ptr::read_volatile(ram.add(idx)) };
+                let len = u16::from_le_bytes([read(0), read(1)]) as usize;
+
+                if len + 2 > PERSIST_RAM_SIZE {
+                    return None;
+                }
+
+                Some((2..2 + len).map(read).collect())
+            }
+        }
+    }
+
+    pub fn save(&self, blob: &[u8]) -> Result<(), PersistError> {
+        match self {
+            Storage::File(path) => match HostFile::create(path) {
+                Some(mut file) => file.write(blob).map_err(|_| PersistError::Write),
+                None => Err(PersistError::Write),
+            },
+            #[cfg(all(target_os = "none", feature = "persist_ram"))]
+            Storage::Ram => {
+                if blob.len() + 2 > PERSIST_RAM_SIZE {
+                    return Err(PersistError::TooLarge);
+                }
+
+                let ram = unsafe { core::ptr::addr_of_mut!(PERSIST_RAM).cast::<u8>() };
+                let len = (blob.len() as u16).to_le_bytes();
+
+                for (idx, b) in len.iter().chain(blob.iter()).enumerate() {
+                    unsafe { core::ptr::write_volatile(ram.add(idx), *b) };
+                }
+                Ok(())
+            }
+        }
+    }
+}
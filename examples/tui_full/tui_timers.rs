@@ -0,0 +1,91 @@
+//! # Timers
+//!
+//! One-shot and periodic timers of the windows, driven by the `Pal::get_timestamp_ms()`;
+//! the windows start them with the `Command::StartTimer`, and get the `AppWindow::on_timer()`
+
+use rtwins::wgt::WId;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Identifies the timer among the timers of the window
+pub type TimerToken = u16;
+
+struct Timer {
+    owner: WId,
+    token: TimerToken,
+    deadline: u32,
+    /// Some for the periodic timer
+    period: Option<u32>,
+}
+
+/// Returns true if the `deadline` is reached; safe for the timestamp wrap-around
+fn is_due(deadline: u32, now: u32) -> bool {
+    now.wrapping_sub(deadline) as i32 >= 0
+}
+
+#[derive(Default)]
+pub struct TimerService {
+    timers: Vec<Timer>,
+}
+
+impl TimerService {
+    /// Starts the timer, firing after `interval_ms`, then every `interval_ms` if `periodic`;
+    /// the running timer of the same owner and token is restarted
+    pub fn start(
+        &mut self,
+        owner: WId,
+        token: TimerToken,
+        interval_ms: u32,
+        periodic: bool,
+        now: u32,
+    ) {
+        self.stop(owner, token);
+        self.timers.push(Timer {
+            owner,
+            token,
+            deadline: now.wrapping_add(interval_ms),
+            period: periodic.then_some(interval_ms.max(1)),
+        });
+    }
+
+    pub fn stop(&mut self, owner: WId, token: TimerToken) {
+        self.timers.retain(|t| t.owner != owner || t.token != token);
+    }
+
+    /// Stops all the timers of the window
+    pub fn stop_all(&mut self, owner: WId) {
+        self.timers.retain(|t| t.owner != owner);
+    }
+
+    /// Returns the time to the nearest deadline, or None if no timer is running
+    pub fn next_timeout_ms(&self, now: u32) -> Option<u32> {
+        self.timers
+            .iter()
+            .map(|t| if is_due(t.deadline, now) { 0 } else { t.deadline.wrapping_sub(now) })
+            .min()
+    }
+
+    /// Returns the owners and the tokens of the timers that are due;
+    /// one-shot timers are removed, periodic are rescheduled
+    pub fn take_expired(&mut self, now: u32) -> Vec<(WId, TimerToken)> {
+        let mut expired = Vec::new();
+
+        for t in self.timers.iter_mut().filter(|t| is_due(t.deadline, now)) {
+            expired.push((t.owner, t.token));
+
+            if let Some(period) = t.period {
+                t.deadline = t.deadline.wrapping_add(period);
+                // after the long break, do not try to catch up
+                if is_due(t.deadline, now) {
+                    t.deadline = now.wrapping_add(period);
+                }
+            }
+        }
+
+        self.timers.retain(|t| t.period.is_some() || !is_due(t.deadline, now));
+        expired
+    }
+}
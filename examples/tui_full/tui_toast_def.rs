@@ -0,0 +1,66 @@
+//! Toast notifications - widgets definition
+
+use rtwins::colors::{ColorBg, ColorFg};
+use rtwins::common::*;
+use rtwins::wgt::prop;
+use rtwins::wgt::*;
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub mod idtst {
+    use rtwins::wgt::{WId, WIDGET_ID_NONE};
+
+    #[rustfmt::skip]
+    rtwins::generate_ids!(
+        WND_TOAST
+            LBL_TOAST_1
+            LBL_TOAST_2
+            LBL_TOAST_3
+            LBL_TOAST_4
+    );
+
+    /// One label per message, from the top
+    pub const LBL_TOASTS: [WId; 4] = [LBL_TOAST_1, LBL_TOAST_2, LBL_TOAST_3, LBL_TOAST_4];
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Message label at given row
+const fn toast_label(id: WId, row: u8) -> Widget {
+    Widget {
+        id,
+        coord: Coord { col: 1, row },
+        size: Size { width: 30, height: 1 },
+        prop: prop::Label {
+            title: "",
+            fg_color: ColorFg::Inherit,
+            bg_color: ColorBg::Inherit,
+        }
+        .into(),
+        ..Widget::cdeflt()
+    }
+}
+
+#[rustfmt::skip]
+const WINDOW_TOAST: Widget = Widget {
+    id: idtst::WND_TOAST,
+    link: Link::cdeflt(),
+    coord: Coord::cdeflt(),
+    size: Size { width: 32, height: 6 },
+    prop: prop::Window {
+        title: "",
+        fg_color: ColorFg::White,
+        bg_color: ColorBg::BlackIntense,
+        is_popup: true,
+    }.into(),
+    children: &[
+        toast_label(idtst::LBL_TOAST_1, 1),
+        toast_label(idtst::LBL_TOAST_2, 2),
+        toast_label(idtst::LBL_TOAST_3, 3),
+        toast_label(idtst::LBL_TOAST_4, 4),
+    ]
+};
+
+/// Toast window widgets tree, translated into the array
+pub const WND_TOAST_WGTS: [Widget; transform::tree_wgt_count(&WINDOW_TOAST)] =
+    transform::tree_to_array(&WINDOW_TOAST);
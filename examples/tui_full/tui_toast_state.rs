@@ -0,0 +1,212 @@
+//! Toast notifications - window state
+
+#![allow(unused_variables)]
+#![allow(dead_code)]
+
+use rtwins::common::*;
+use rtwins::esc;
+use rtwins::wgt::{self, WId, Widget, WindowState, WIDGET_ID_NONE};
+use rtwins::TERM;
+use rtwins::*;
+
+use super::tui_commands::*;
+use super::tui_toast_def::idtst;
+use super::tui_wnd_mngr::AppWindow;
+
+use core::cell::RefCell;
+
+extern crate alloc;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// How long the message is shown [ms]
+pub const TOAST_DURATION_MS: u32 = 3000;
+
+/// Message with the time it was shown at
+struct Toast {
+    severity: Severity,
+    message: String,
+    shown_at: u32,
+}
+
+/// State of the notifications overlay, shown in the top-right corner of the main window
+pub struct ToastState {
+    // id of the window
+    pub wnd_id: WId,
+    /// all window widgets, starting with the window widget itself
+    widgets: &'static [wgt::Widget],
+    /// widgets runtime state
+    pub rs: wgt::RuntimeStates,
+    /// list of widgets to redraw
+    invalidated: Vec<WId>,
+    // overlay coordinates
+    coord: Coord,
+    /// messages, from the oldest one
+    toasts: Vec<Toast>,
+    // app-wide commands queue
+    cmds: Rc<RefCell<CommandsQueue>>,
+}
+
+impl ToastState {
+    pub fn new(wnd_id: WId, widgets: &'static [Widget], cmds: Rc<RefCell<CommandsQueue>>) -> Self {
+        ToastState {
+            wnd_id,
+            widgets,
+            rs: wgt::RuntimeStates::default(),
+            invalidated: Vec::with_capacity(4),
+            coord: Coord::cdeflt(),
+            toasts: Vec::with_capacity(idtst::LBL_TOASTS.len()),
+            cmds,
+        }
+    }
+
+    /// Places the overlay in the top-right corner of the `wnd`
+    pub fn anchor_on(&mut self, wnd: &'static Widget) {
+        let wndtoast = &self.widgets[0];
+        self.coord.col = wnd.coord.col + wnd.size.width.saturating_sub(wndtoast.size.width + 2);
+        self.coord.row = wnd.coord.row + 1;
+    }
+
+    /// Adds the message; if there is no room, the oldest one is dropped
+    pub fn push(&mut self, severity: Severity, message: String, now: u32) {
+        if self.toasts.len() == idtst::LBL_TOASTS.len() {
+            self.toasts.remove(0);
+        }
+
+        self.toasts.push(Toast {
+            severity,
+            message,
+            shown_at: now,
+        });
+        self.invalidate(wgt::WIDGET_ID_ALL);
+    }
+
+    /// Removes the messages shown for too long; returns true if any was removed
+    pub fn expire(&mut self, now: u32) -> bool {
+        let count = self.toasts.len();
+        self.toasts.retain(|t| now.wrapping_sub(t.shown_at) < TOAST_DURATION_MS);
+
+        if self.toasts.len() == count {
+            return false;
+        }
+
+        self.invalidate(wgt::WIDGET_ID_ALL);
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    /// Index of the message shown on the label
+    fn toast_idx(wid: WId) -> Option<usize> {
+        idtst::LBL_TOASTS.iter().position(|id| *id == wid)
+    }
+}
+
+// -----------------------------------------------------------------------------------------------
+
+impl AppWindow for ToastState {}
+
+impl rtwins::wgt::WindowState for ToastState {
+    /** common state queries **/
+
+    fn is_enabled(&self, wgt: &Widget) -> bool {
+        self.rs.get_enabled_or_default(wgt.id)
+    }
+
+    fn is_focused(&self, wgt: &Widget) -> bool {
+        false
+    }
+
+    fn is_visible(&self, wgt: &Widget) -> bool {
+        match Self::toast_idx(wgt.id) {
+            Some(idx) => idx < self.toasts.len(),
+            None => true,
+        }
+    }
+
+    fn get_focused_id(&mut self) -> WId {
+        WIDGET_ID_NONE
+    }
+
+    fn set_focused_id(&mut self, wid: WId) {}
+
+    fn get_widgets(&self) -> &'static [Widget] {
+        self.widgets
+    }
+
+    fn get_rstate(&mut self) -> Option<&mut wgt::RuntimeStates> {
+        Some(&mut self.rs)
+    }
+
+    /** widget-specific queries; all mutable params are outputs **/
+
+    fn get_window_coord(&mut self) -> Coord {
+        self.coord
+    }
+
+    fn get_window_size(&mut self) -> Size {
+        // the frame shrinks to the number of messages
+        let mut sz = self.widgets.first().unwrap().size;
+        sz.height = 2 + self.toasts.len() as u8;
+        sz
+    }
+
+    fn get_label_text(&mut self, wgt: &Widget, out: &mut String) {
+        if let Some(toast) = Self::toast_idx(wgt.id).and_then(|idx| self.toasts.get(idx)) {
+            let (bg, fg) = match toast.severity {
+                Severity::Info => (esc::BG_NAVY, esc::FG_WHITE),
+                Severity::Warning => (esc::BG_ORANGE, esc::FG_BLACK),
+                Severity::Error => (esc::BG_RED, esc::FG_WHITE),
+            };
+            out.push_str(bg);
+            out.push_str(fg);
+
+            let width = wgt.size.width as usize;
+            let mut nchars = 0;
+            for ch in toast.message.chars().take(width) {
+                out.push(ch);
+                nchars += 1;
+            }
+            for _ in nchars..width {
+                out.push(' ');
+            }
+
+            out.push_str(esc::FG_DEFAULT);
+            out.push_str(esc::BG_DEFAULT);
+        }
+    }
+
+    /* requests */
+
+    fn invalidate_many(&mut self, wids: &[WId]) {
+        // iterate, to avoid adding the same ID twice
+        for wid in wids.iter() {
+            if !self.invalidated.contains(wid) {
+                self.invalidated.push(*wid);
+            }
+        }
+    }
+
+    fn instant_redraw(&mut self, wid: WId) {
+        if let Some(mut term_guard) = TERM.try_lock() {
+            term_guard.draw(self, &[wid]);
+            term_guard.flush_buff();
+        }
+        else {
+            rtwins::tr_warn!("Cannot lock the term");
+        }
+    }
+
+    fn clear_invalidated(&mut self) {
+        self.invalidated.clear();
+    }
+
+    fn get_invalidated(&mut self, out: &mut Vec<WId>) {
+        core::mem::swap(&mut self.invalidated, out);
+    }
+}
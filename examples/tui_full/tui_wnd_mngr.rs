@@ -0,0 +1,547 @@
+//! Demo - window manager
+//!
+//! Registry of the application windows: the windows are added at runtime,
+//! getting the id that is their index in the registry, and can be removed when no longer needed
+
+use rtwins::common::Coord;
+use rtwins::wgt::{self, WId, Widget, WindowState};
+use rtwins::wnd_manager::WindowManager;
+
+use super::tui_commands::{Command, CommandsQueue, DialogResult, DialogToken};
+use super::tui_timers::{TimerService, TimerToken};
+use crate::draw_debug;
+use rtwins::TERM;
+
+use core::any::Any;
+use core::cell::RefCell;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Conversions of the concrete window to the trait objects; implemented for every window
+pub trait AsWindowState {
+    fn as_ws(&self) -> &dyn WindowState;
+    fn as_ws_mut(&mut self) -> &mut dyn WindowState;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: WindowState + 'static> AsWindowState for T {
+    fn as_ws(&self) -> &dyn WindowState {
+        self
+    }
+
+    fn as_ws_mut(&mut self) -> &mut dyn WindowState {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Window managed by the `WndMngr`; the hooks have the default, empty implementation
+pub trait AppWindow: AsWindowState {
+    /// Called for every registered window, when the terminal size changes
+    fn on_screen_resize(&mut self, _cols: u16, _rows: u16) {}
+
+    /// Called with the outcome of the dialog this window requested
+    fn on_dialog_result(&mut self, _token: DialogToken, _result: DialogResult) {}
+
+    /// Called when the timer started by this window expires
+    fn on_timer(&mut self, _token: TimerToken) {}
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Location of the popup window, shared by all the popups
+pub struct PopupPlacement {
+    /// the popup window widget
+    popup: &'static Widget,
+    /// popup coordinates
+    pub coord: Coord,
+    /// window the popup is centered on
+    center_wnd: Option<&'static Widget>,
+    /// number of the popups below this one; each is shifted down-right
+    stack_level: u8,
+}
+
+impl PopupPlacement {
+    pub fn new(popup: &'static Widget) -> Self {
+        PopupPlacement {
+            popup,
+            coord: Coord::cdeflt(),
+            center_wnd: None,
+            stack_level: 0,
+        }
+    }
+
+    /// Sets the number of the popups below this one; call before the `center_on()`
+    pub fn set_stack_level(&mut self, level: u8) {
+        self.stack_level = level;
+    }
+
+    /// Places the popup on the window center, shifted by the stack level
+    pub fn center_on(&mut self, wnd: &'static Widget) {
+        self.center_wnd = Some(wnd);
+        let col = wnd.size.width.saturating_sub(self.popup.size.width) / 2;
+        let row = wnd.size.height.saturating_sub(self.popup.size.height) / 2;
+        // the popups below stay visible
+        self.coord.col = col
+            .saturating_add(wnd.coord.col)
+            .saturating_add(self.stack_level.saturating_mul(2));
+        self.coord.row = row
+            .saturating_add(wnd.coord.row)
+            .saturating_add(self.stack_level);
+    }
+
+    /// Moves the popup up/left, so it fits the screen of `cols` x `rows`, if possible
+    pub fn keep_on_screen(&mut self, cols: u16, rows: u16) {
+        let max_col = cols.saturating_sub(self.popup.size.width as u16);
+        let max_row = rows.saturating_sub(self.popup.size.height as u16);
+        self.coord.col = (self.coord.col as u16).min(max_col) as u8;
+        self.coord.row = (self.coord.row as u16).min(max_row) as u8;
+    }
+
+    /// Centers the popup again, then keeps it on the screen of the new size
+    pub fn on_screen_resize(&mut self, cols: u16, rows: u16) {
+        if let Some(wnd) = self.center_wnd {
+            self.center_on(wnd);
+        }
+        self.keep_on_screen(cols, rows);
+    }
+}
+
+/// Closes the popup, sending the result to the owner, if any
+pub fn close_popup(
+    cmds: &RefCell<CommandsQueue>,
+    wnd_id: WId,
+    owner: Option<WId>,
+    token: DialogToken,
+    result: DialogResult,
+) {
+    match cmds.try_borrow_mut() {
+        Ok(ref mut cmds) => {
+            // close first, so the popup opened by the owner is the top one
+            cmds.push(Command::HidePopup { wnd_id });
+
+            if owner.is_some() {
+                cmds.push(Command::DialogResult {
+                    owner,
+                    token,
+                    result,
+                });
+            }
+        }
+        Err(_) => rtwins::tr_err!("Cannot borrow commands"),
+    }
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Screen rectangle
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Area {
+    col: u16,
+    row: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Area {
+    fn intersects(&self, other: &Area) -> bool {
+        self.col < other.col + other.width
+            && other.col < self.col + self.width
+            && self.row < other.row + other.height
+            && other.row < self.row + self.height
+    }
+
+    fn contains(&self, other: &Area) -> bool {
+        self.col <= other.col
+            && self.row <= other.row
+            && self.col + self.width >= other.col + other.width
+            && self.row + self.height >= other.row + other.height
+    }
+}
+
+/// Screen area of the widget
+fn widget_area(ws: &dyn WindowState, wgt: &wgt::Widget) -> Area {
+    let coord = wgt::get_screen_coord(ws, wgt);
+
+    Area {
+        col: coord.col as u16,
+        row: coord.row as u16,
+        width: wgt.size.width as u16,
+        height: wgt.size.height as u16,
+    }
+}
+
+/// Screen area of the whole window
+fn window_area(ws: &dyn WindowState) -> Option<Area> {
+    ws.get_widgets().first().map(|wnd| widget_area(ws, wnd))
+}
+
+/// Overlay window and the area it was drawn on; None until drawn as a whole
+struct Overlay {
+    wnd_id: WId,
+    area: Option<Area>,
+}
+
+// ---------------------------------------------------------------------------------------------- //
+
+pub struct WndMngr {
+    /// app-wide commands queue, shared with all windows
+    pub cmdque: Rc<RefCell<CommandsQueue>>,
+    /// timers of all the windows
+    pub timers: TimerService,
+    /// registered windows, indexed with the window id; None for the removed ones
+    windows: Vec<Option<Box<dyn AppWindow>>>,
+    /// visible windows, from bottom to top
+    visible: Vec<WId>,
+    /// non-modal windows drawn over the visible ones; they get no input
+    overlays: Vec<Overlay>,
+    /// terminal size from the last `relayout()`
+    screen_size: Option<(u16, u16)>,
+}
+
+impl WndMngr {
+    pub fn new() -> Self {
+        WndMngr {
+            cmdque: Rc::new(RefCell::new(CommandsQueue::default())),
+            timers: TimerService::default(),
+            windows: Vec::new(),
+            visible: Vec::new(),
+            overlays: Vec::new(),
+            screen_size: None,
+        }
+    }
+
+    /// Registers the window returned by `create`, that gets the assigned id
+    /// and the commands queue; returns the window id
+    pub fn add<F>(&mut self, create: F) -> WId
+    where
+        F: FnOnce(WId, Rc<RefCell<CommandsQueue>>) -> Box<dyn AppWindow>,
+    {
+        let idx = self
+            .windows
+            .iter()
+            .position(|w| w.is_none())
+            .unwrap_or(self.windows.len());
+        let mut wnd = create(idx as WId, Rc::clone(&self.cmdque));
+
+        if let Some((cols, rows)) = self.screen_size {
+            wnd.on_screen_resize(cols, rows);
+        }
+
+        if idx == self.windows.len() {
+            self.windows.push(Some(wnd));
+        }
+        else {
+            self.windows[idx] = Some(wnd);
+        }
+
+        idx as WId
+    }
+
+    /// Hides and unregisters the window; its id may be reused by the next `add()`
+    pub fn remove(&mut self, wnd_id: WId) -> Option<Box<dyn AppWindow>> {
+        if self.visible.contains(&wnd_id) {
+            self.hide(wnd_id);
+            // the windows below were repainted
+            self.reset_overlays();
+            self.draw_overlays(&[]);
+        }
+
+        if self.is_overlay_visible(wnd_id) {
+            self.hide_overlay(wnd_id);
+        }
+
+        self.timers.stop_all(wnd_id);
+        self.windows.get_mut(wnd_id as usize).and_then(|w| w.take())
+    }
+
+    /// Returns the window of given type
+    pub fn get_as<T: 'static>(&self, wnd_id: WId) -> Option<&T> {
+        self.get_wnd(wnd_id)
+            .and_then(|w| w.as_any().downcast_ref::<T>())
+    }
+
+    /// Returns the window of given type
+    pub fn get_as_mut<T: 'static>(&mut self, wnd_id: WId) -> Option<&mut T> {
+        self.get_wnd_mut(wnd_id)
+            .and_then(|w| w.as_any_mut().downcast_mut::<T>())
+    }
+
+    pub fn get_wnd(&self, wnd_id: WId) -> Option<&dyn AppWindow> {
+        match self.windows.get(wnd_id as usize) {
+            Some(Some(wnd)) => Some(wnd.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn get_wnd_mut(&mut self, wnd_id: WId) -> Option<&mut dyn AppWindow> {
+        match self.windows.get_mut(wnd_id as usize) {
+            Some(Some(wnd)) => Some(wnd.as_mut()),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the visible windows, from bottom to top
+    pub fn iter(&self) -> impl Iterator<Item = (WId, &dyn AppWindow)> + '_ {
+        self.visible
+            .iter()
+            .filter_map(|wid| self.get_wnd(*wid).map(|w| (*wid, w)))
+    }
+
+    /// Lets all the windows adjust to the new terminal size;
+    /// windows added later are adjusted when registered
+    pub fn relayout(&mut self, cols: u16, rows: u16) {
+        self.screen_size = Some((cols, rows));
+        self.windows
+            .iter_mut()
+            .flatten()
+            .for_each(|w| w.on_screen_resize(cols, rows));
+    }
+
+    /// Draws the invalidated widgets of the top window, like the `WindowManager` does;
+    /// in the draw debugging mode, they are highlighted first.
+    /// The overlays are redrawn only if the area below them was repainted
+    pub fn draw_top_invalidated(&mut self) {
+        if draw_debug::is_enabled() {
+            if let Some(ws) = self.get_top_mut() {
+                draw_debug::flash_invalidated(ws);
+            }
+        }
+
+        let damaged = match self.get_top_mut() {
+            Some(ws) => Self::invalidated_areas(ws),
+            None => Vec::new(),
+        };
+
+        WindowManager::draw_top_invalidated(self);
+        self.draw_overlays(&damaged);
+    }
+
+    /// Draws all the visible windows, from bottom to top, then the overlays
+    pub fn draw_all(&mut self) {
+        self.flash_visible();
+        WindowManager::draw_all(self);
+        self.reset_overlays();
+        self.draw_overlays(&[]);
+    }
+
+    /// Shows the window on top of the visible ones, like the `WindowManager` does;
+    /// in the draw debugging mode, it is highlighted first
+    pub fn show(&mut self, wnd_id: WId) {
+        if draw_debug::is_enabled() {
+            if let Some(ws) = self.get_ref(wnd_id) {
+                draw_debug::flash_window(ws);
+            }
+        }
+
+        WindowManager::show(self, wnd_id);
+    }
+
+    /// Hides the window, like the `WindowManager` does;
+    /// in the draw debugging mode, the windows below, being repainted, are highlighted first
+    pub fn hide(&mut self, wnd_id: WId) {
+        if draw_debug::is_enabled() && self.visible.contains(&wnd_id) {
+            self.visible
+                .iter()
+                .filter(|wid| **wid != wnd_id)
+                .filter_map(|wid| self.get_ref(*wid))
+                .for_each(draw_debug::flash_window);
+        }
+
+        WindowManager::hide(self, wnd_id);
+    }
+
+    /// Shows the window over the visible ones, without taking the input from the top one
+    pub fn show_overlay(&mut self, wnd_id: WId) {
+        if !self.is_overlay_visible(wnd_id) {
+            self.overlays.push(Overlay { wnd_id, area: None });
+        }
+
+        self.draw_overlays(&[]);
+    }
+
+    /// Hides the overlay window; the area below is restored
+    pub fn hide_overlay(&mut self, wnd_id: WId) {
+        if let Some(idx) = self.overlays.iter().position(|ov| ov.wnd_id == wnd_id) {
+            match self.overlays.remove(idx).area {
+                Some(area) => {
+                    self.restore_area(area);
+                    self.draw_overlays(&[area]);
+                }
+                None => self.draw_all(),
+            }
+        }
+    }
+
+    pub fn is_overlay_visible(&self, wnd_id: WId) -> bool {
+        self.overlays.iter().any(|ov| ov.wnd_id == wnd_id)
+    }
+
+    /// Draws the changes of the overlays; the overlay that was resized or moved
+    /// is drawn as a whole, after the area it covered before is restored
+    pub fn update_overlays(&mut self) {
+        self.draw_overlays(&[]);
+    }
+
+    /// In the draw debugging mode, highlights all the visible windows
+    fn flash_visible(&self) {
+        if draw_debug::is_enabled() {
+            self.visible
+                .iter()
+                .filter_map(|wid| self.get_ref(*wid))
+                .for_each(draw_debug::flash_window);
+        }
+    }
+
+    /// Makes the overlays drawn as a whole, after the windows below were repainted
+    fn reset_overlays(&mut self) {
+        self.overlays.iter_mut().for_each(|ov| ov.area = None);
+    }
+
+    /// Screen areas of the invalidated widgets
+    fn invalidated_areas(ws: &mut dyn WindowState) -> Vec<Area> {
+        let mut wids: Vec<WId> = Vec::new();
+        ws.get_invalidated(&mut wids);
+        let ws: &dyn WindowState = ws;
+
+        if wids.contains(&wgt::WIDGET_ID_ALL) {
+            return window_area(ws).into_iter().collect();
+        }
+
+        wids.iter()
+            .filter_map(|wid| wgt::find_by_id(ws.get_widgets(), *wid))
+            .filter(|wgt| ws.is_visible(wgt))
+            .map(|wgt| widget_area(ws, wgt))
+            .collect()
+    }
+
+    /// Repaints the visible windows in the area, from bottom to top; of every window,
+    /// only the innermost widget containing the area is drawn, if there is one
+    fn restore_area(&mut self, area: Area) {
+        for idx in 0..self.visible.len() {
+            let wnd_id = self.visible[idx];
+
+            let ws = match self.get_mut(wnd_id) {
+                Some(ws) => ws,
+                None => continue,
+            };
+
+            if !window_area(ws).map_or(false, |wa| wa.intersects(&area)) {
+                continue;
+            }
+
+            // the widgets are ordered parent first, so the last one is the innermost
+            let wid = ws
+                .get_widgets()
+                .iter()
+                .filter(|wgt| ws.is_visible(wgt) && widget_area(ws, wgt).contains(&area))
+                .last()
+                .map_or(wgt::WIDGET_ID_ALL, |wgt| wgt.id);
+
+            if let Some(mut term_guard) = TERM.try_lock() {
+                term_guard.draw(ws, &[wid]);
+                term_guard.flush_buff();
+            }
+            else {
+                rtwins::tr_warn!("Cannot lock the term");
+            }
+        }
+    }
+
+    /// Draws the overlays: the new, moved or lying on the `damaged` areas as a whole,
+    /// other ones only their invalidated widgets
+    fn draw_overlays(&mut self, damaged: &[Area]) {
+        let mut damaged: Vec<Area> = damaged.to_vec();
+
+        // the areas left by the shrunk or moved overlays
+        for idx in 0..self.overlays.len() {
+            let wnd_id = self.overlays[idx].wnd_id;
+            let area = self.get_ref(wnd_id).and_then(window_area);
+
+            if let Some(prev) = self.overlays[idx].area.filter(|prev| Some(*prev) != area) {
+                self.restore_area(prev);
+                self.overlays[idx].area = None;
+                damaged.push(prev);
+            }
+        }
+
+        for idx in 0..self.overlays.len() {
+            let wnd_id = self.overlays[idx].wnd_id;
+            let prev_area = self.overlays[idx].area;
+
+            let ws = match self.get_mut(wnd_id) {
+                Some(ws) => ws,
+                None => continue,
+            };
+
+            let area = window_area(ws);
+            let redraw_all = match (prev_area, area) {
+                (Some(prev), Some(_)) => damaged.iter().any(|dmg| dmg.intersects(&prev)),
+                _ => true,
+            };
+
+            let mut wids: Vec<WId> = Vec::new();
+
+            if redraw_all {
+                wids.push(wgt::WIDGET_ID_ALL);
+            }
+            else {
+                ws.get_invalidated(&mut wids);
+            }
+
+            ws.clear_invalidated();
+
+            if wids.is_empty() {
+                continue;
+            }
+
+            if let Some(mut term_guard) = TERM.try_lock() {
+                term_guard.draw(ws, &wids);
+                term_guard.flush_buff();
+            }
+            else {
+                rtwins::tr_warn!("Cannot lock the term");
+            }
+
+            self.overlays[idx].area = area;
+
+            // the overlays above are drawn over it
+            if let Some(area) = area.filter(|_| redraw_all) {
+                damaged.push(area);
+            }
+        }
+    }
+}
+
+impl WindowManager for WndMngr {
+    fn get_ref(&self, wnd_id: WId) -> Option<&dyn WindowState> {
+        self.get_wnd(wnd_id).map(|w| w.as_ws())
+    }
+
+    fn get_mut(&mut self, wnd_id: WId) -> Option<&mut dyn WindowState> {
+        self.get_wnd_mut(wnd_id).map(|w| w.as_ws_mut())
+    }
+
+    #[inline]
+    fn get_visible(&self) -> &[WId] {
+        &self.visible[..]
+    }
+
+    #[inline]
+    fn get_visible_mut(&mut self) -> &mut Vec<WId> {
+        &mut self.visible
+    }
+}
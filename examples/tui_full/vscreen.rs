@@ -0,0 +1,557 @@
+//! # Virtual VT100 screen
+//!
+//! In-memory model of the terminal screen, built from the escape-code stream
+//! produced by the `rtwins::Term`
+
+#![allow(dead_code)]
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// ---------------------------------------------------------------------------------------------- //
+
+/// Cell foreground/background color
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Color {
+    #[default]
+    Default,
+    /// one of the 256 palette colors; 0..15 are the basic ones
+    Idx(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// Cell attributes bits
+pub mod attr {
+    pub const BOLD: u8 = 0x01;
+    pub const FAINT: u8 = 0x02;
+    pub const ITALICS: u8 = 0x04;
+    pub const UNDERLINE: u8 = 0x08;
+    pub const BLINK: u8 = 0x10;
+    pub const INVERSE: u8 = 0x20;
+}
+
+/// Single screen character with it's style
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cell {
+    /// `'\0'` marks the right half of a double-width character
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub attr: u8,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            ch: ' ',
+            fg: Color::Default,
+            bg: Color::Default,
+            attr: 0,
+        }
+    }
+}
+
+impl Cell {
+    pub fn same_style(&self, other: &Cell) -> bool {
+        self.fg == other.fg && self.bg == other.bg && self.attr == other.attr
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ParserState {
+    Ground,
+    Esc,
+    EscCharset,
+    Csi,
+    Osc,
+    OscEsc,
+}
+
+/// Screen grid with the cursor and the current drawing style
+#[derive(Clone)]
+pub struct VScreen {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    /// bit set for every cell changed since the last `clear_dirty()`
+    dirty: Vec<u32>,
+    cursor_col: u16,
+    cursor_row: u16,
+    saved_cursor: (u16, u16),
+    /// style applied to the written characters
+    style: Cell,
+    /// enabled DEC private modes, like mouse reporting `1000`
+    modes: Vec<u16>,
+    state: ParserState,
+    seq: String,
+}
+
+impl VScreen {
+    pub fn new(width: u16, height: u16) -> Self {
+        VScreen {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+            dirty: vec![0; (width as usize * height as usize + 31) / 32],
+            cursor_col: 0,
+            cursor_row: 0,
+            saved_cursor: (0, 0),
+            style: Cell::default(),
+            modes: Vec::new(),
+            state: ParserState::Ground,
+            seq: String::new(),
+        }
+    }
+
+    /** queries **/
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns 0-based (col, row) cursor position
+    pub fn cursor(&self) -> (u16, u16) {
+        (self.cursor_col, self.cursor_row)
+    }
+
+    pub fn cell(&self, col: u16, row: u16) -> Option<&Cell> {
+        if col < self.width && row < self.height {
+            self.cells.get(row as usize * self.width as usize + col as usize)
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn is_mode_set(&self, mode: u16) -> bool {
+        self.modes.contains(&mode)
+    }
+
+    /// Style applied to the written characters
+    pub fn style(&self) -> &Cell {
+        &self.style
+    }
+
+    /// Returns (col, row) of the changed cells, row by row
+    pub fn dirty_cells(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        (0..self.cells.len())
+            .filter(|idx| self.dirty[idx / 32] & (1 << (idx % 32)) != 0)
+            .map(|idx| ((idx % self.width as usize) as u16, (idx / self.width as usize) as u16))
+    }
+
+    /// Returns given row characters, without trailing spaces
+    pub fn row_text(&self, row: u16) -> String {
+        let mut out = String::with_capacity(self.width as usize);
+
+        for col in 0..self.width {
+            match self.cell(col, row) {
+                Some(c) if c.ch != '\0' => out.push(c.ch),
+                _ => {}
+            }
+        }
+
+        out.truncate(out.trim_end().len());
+        out
+    }
+
+    /// Returns all rows, separated with new line
+    pub fn text(&self) -> String {
+        let mut out = String::with_capacity(self.cells.len());
+
+        for row in 0..self.height {
+            out.push_str(&self.row_text(row));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Returns all rows with SGR sequences reproducing the cells style
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::with_capacity(self.cells.len() * 2);
+
+        for row in 0..self.height {
+            let row_start = row as usize * self.width as usize;
+            let row_cells = &self.cells[row_start..][..self.width as usize];
+            let used = row_cells
+                .iter()
+                .rposition(|c| *c != Cell::default())
+                .map_or(0, |p| p + 1);
+            let mut style = Cell::default();
+
+            for c in row_cells[..used].iter().filter(|c| c.ch != '\0') {
+                if !c.same_style(&style) {
+                    out.push_str(&sgr_encode(c));
+                    style = *c;
+                }
+                out.push(c.ch);
+            }
+
+            out.push_str("\x1b[0m\n");
+        }
+
+        out
+    }
+
+    /// Returns (col, row) of the first occurence of `needle`
+    pub fn find_text(&self, needle: &str) -> Option<(u16, u16)> {
+        let needle: Vec<char> = needle.chars().collect();
+        if needle.is_empty() {
+            return None;
+        }
+
+        for row in 0..self.height {
+            let chars: Vec<(u16, char)> = (0..self.width)
+                .filter_map(|col| self.cell(col, row).map(|c| (col, c.ch)))
+                .filter(|(_, ch)| *ch != '\0')
+                .collect();
+
+            if let Some(pos) = chars
+                .windows(needle.len())
+                .position(|w| w.iter().map(|(_, ch)| *ch).eq(needle.iter().copied()))
+            {
+                return Some((chars[pos].0, row));
+            }
+        }
+
+        None
+    }
+
+    /** modifiers **/
+
+    pub fn clear(&mut self) {
+        for idx in 0..self.cells.len() {
+            self.set_cell(idx, Cell::default());
+        }
+        self.cursor_col = 0;
+        self.cursor_row = 0;
+    }
+
+    /// Changes the size; the content is cleared and all cells are marked as changed
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let len = width as usize * height as usize;
+        self.width = width;
+        self.height = height;
+        // free the old buffers first; on target, the heap cannot hold both
+        self.cells = Vec::new();
+        self.dirty = Vec::new();
+        self.cells = vec![Cell::default(); len];
+        self.dirty = vec![0; (len + 31) / 32];
+        self.mark_all_dirty();
+        self.cursor_col = self.cursor_col.min(width.saturating_sub(1));
+        self.cursor_row = self.cursor_row.min(height.saturating_sub(1));
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        for idx in 0..self.cells.len() {
+            self.dirty[idx / 32] |= 1 << (idx % 32);
+        }
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty.iter_mut().for_each(|d| *d = 0);
+    }
+
+    /// Interprets the terminal output
+    pub fn write_str(&mut self, s: &str) {
+        for ch in s.chars() {
+            self.feed(ch);
+        }
+    }
+
+    fn feed(&mut self, ch: char) {
+        match self.state {
+            ParserState::Ground => match ch {
+                '\x1b' => self.state = ParserState::Esc,
+                '\r' => self.cursor_col = 0,
+                // output post-processing (ONLCR) translates LF into CR-LF
+                '\n' => {
+                    self.cursor_col = 0;
+                    self.line_feed();
+                }
+                '\x08' => self.cursor_col = self.cursor_col.saturating_sub(1),
+                '\t' => {
+                    let next_stop = (self.cursor_col / 8 + 1).saturating_mul(8);
+                    self.cursor_col = next_stop.min(self.width.saturating_sub(1));
+                }
+                c if (c as u32) < 0x20 || c == '\x7f' => {}
+                c => self.put_char(c),
+            },
+            ParserState::Esc => {
+                self.state = ParserState::Ground;
+
+                match ch {
+                    '[' => {
+                        self.seq.clear();
+                        self.state = ParserState::Csi;
+                    }
+                    ']' => self.state = ParserState::Osc,
+                    '(' | ')' => self.state = ParserState::EscCharset,
+                    '7' => self.saved_cursor = (self.cursor_col, self.cursor_row),
+                    '8' => (self.cursor_col, self.cursor_row) = self.saved_cursor,
+                    'c' => {
+                        self.clear();
+                        self.style = Cell::default();
+                        self.modes.clear();
+                    }
+                    _ => {}
+                }
+            }
+            ParserState::EscCharset => self.state = ParserState::Ground,
+            ParserState::Csi => {
+                if ('\x40'..='\x7e').contains(&ch) {
+                    self.state = ParserState::Ground;
+                    let params = core::mem::take(&mut self.seq);
+                    self.exec_csi(&params, ch);
+                }
+                else {
+                    self.seq.push(ch);
+                }
+            }
+            // OSC ends with BEL or ST (ESC \)
+            ParserState::Osc => match ch {
+                '\x07' => self.state = ParserState::Ground,
+                '\x1b' => self.state = ParserState::OscEsc,
+                _ => {}
+            },
+            ParserState::OscEsc => self.state = ParserState::Ground,
+        }
+    }
+
+    fn put_char(&mut self, ch: char) {
+        let w = char_width(ch);
+        // nothing to draw on
+        if w == 0 || self.cells.is_empty() {
+            return;
+        }
+
+        if self.cursor_col.saturating_add(w as u16) > self.width {
+            self.cursor_col = 0;
+            self.line_feed();
+        }
+
+        let idx = self.cursor_row as usize * self.width as usize + self.cursor_col as usize;
+        self.set_cell(idx, Cell { ch, ..self.style });
+        if w == 2 {
+            self.set_cell(
+                idx + 1,
+                Cell {
+                    ch: '\0',
+                    ..self.style
+                },
+            );
+        }
+
+        self.cursor_col = self.cursor_col.saturating_add(w as u16);
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor_row.saturating_add(1) < self.height {
+            self.cursor_row += 1;
+        }
+        else {
+            // scroll up
+            let w = self.width as usize;
+            let blank = self.blank();
+            self.cells.drain(..w);
+            self.cells.extend(core::iter::repeat(blank).take(w));
+            self.mark_all_dirty();
+        }
+    }
+
+    fn set_cell(&mut self, idx: usize, cell: Cell) {
+        if self.cells[idx] != cell {
+            self.cells[idx] = cell;
+            self.dirty[idx / 32] |= 1 << (idx % 32);
+            // the other half of the wide character changes as well
+            if cell.ch == '\0' && idx > 0 {
+                self.dirty[(idx - 1) / 32] |= 1 << ((idx - 1) % 32);
+            }
+        }
+    }
+
+    /// Erased cell has the current background (BCE)
+    fn blank(&self) -> Cell {
+        Cell {
+            bg: self.style.bg,
+            ..Cell::default()
+        }
+    }
+
+    fn erase(&mut self, from: usize, to: usize) {
+        let blank = self.blank();
+        let to = to.min(self.cells.len());
+
+        for idx in from.min(to)..to {
+            self.set_cell(idx, blank);
+        }
+    }
+
+    fn exec_csi(&mut self, params: &str, cmd: char) {
+        let private = params.starts_with('?');
+        let nums: Vec<u16> = params
+            .trim_start_matches(|c| c == '?' || c == '>' || c == '<')
+            .split(';')
+            .map(|p| p.parse::<u16>().unwrap_or(0))
+            .collect();
+        // first param, at least 1
+        let n = nums.first().copied().unwrap_or(0).max(1);
+        let cur_idx = self.cursor_row as usize * self.width as usize + self.cursor_col as usize;
+        let row_start = self.cursor_row as usize * self.width as usize;
+        let last_row = self.height.saturating_sub(1);
+        let last_col = self.width.saturating_sub(1);
+
+        match cmd {
+            'h' | 'l' if private => {
+                for mode in nums.iter() {
+                    self.modes.retain(|m| m != mode);
+                    if cmd == 'h' {
+                        self.modes.push(*mode);
+                    }
+                }
+            }
+            'A' => self.cursor_row = self.cursor_row.saturating_sub(n),
+            'B' => self.cursor_row = self.cursor_row.saturating_add(n).min(last_row),
+            'C' => self.cursor_col = self.cursor_col.saturating_add(n).min(last_col),
+            'D' => self.cursor_col = self.cursor_col.saturating_sub(n),
+            'G' => self.cursor_col = (n - 1).min(last_col),
+            'd' => self.cursor_row = (n - 1).min(last_row),
+            'H' | 'f' => {
+                let row = nums.first().copied().unwrap_or(1).max(1);
+                let col = nums.get(1).copied().unwrap_or(1).max(1);
+                self.cursor_row = (row - 1).min(last_row);
+                self.cursor_col = (col - 1).min(last_col);
+            }
+            'J' => match nums.first().copied().unwrap_or(0) {
+                0 => self.erase(cur_idx, self.cells.len()),
+                1 => self.erase(0, cur_idx + 1),
+                _ => self.erase(0, self.cells.len()),
+            },
+            'K' => match nums.first().copied().unwrap_or(0) {
+                0 => self.erase(cur_idx, row_start + self.width as usize),
+                1 => self.erase(row_start, cur_idx + 1),
+                _ => self.erase(row_start, row_start + self.width as usize),
+            },
+            'X' => self.erase(
+                cur_idx,
+                (cur_idx + n as usize).min(row_start + self.width as usize),
+            ),
+            's' => self.saved_cursor = (self.cursor_col, self.cursor_row),
+            'u' => (self.cursor_col, self.cursor_row) = self.saved_cursor,
+            'm' => self.exec_sgr(&nums),
+            _ => {}
+        }
+    }
+
+    fn exec_sgr(&mut self, nums: &[u16]) {
+        let mut i = 0;
+
+        while i < nums.len() {
+            let st = &mut self.style;
+
+            match nums[i] {
+                0 => *st = Cell::default(),
+                1 => st.attr |= attr::BOLD,
+                2 => st.attr |= attr::FAINT,
+                3 => st.attr |= attr::ITALICS,
+                4 => st.attr |= attr::UNDERLINE,
+                5 => st.attr |= attr::BLINK,
+                7 => st.attr |= attr::INVERSE,
+                22 => st.attr &= !(attr::BOLD | attr::FAINT),
+                23 => st.attr &= !attr::ITALICS,
+                24 => st.attr &= !attr::UNDERLINE,
+                25 => st.attr &= !attr::BLINK,
+                27 => st.attr &= !attr::INVERSE,
+                p @ 30..=37 => st.fg = Color::Idx((p - 30) as u8),
+                p @ 40..=47 => st.bg = Color::Idx((p - 40) as u8),
+                p @ 90..=97 => st.fg = Color::Idx((p - 90 + 8) as u8),
+                p @ 100..=107 => st.bg = Color::Idx((p - 100 + 8) as u8),
+                39 => st.fg = Color::Default,
+                49 => st.bg = Color::Default,
+                p @ (38 | 48) => {
+                    let cl = match nums.get(i + 1) {
+                        Some(5) => {
+                            i += 2;
+                            Color::Idx(nums.get(i).copied().unwrap_or(0) as u8)
+                        }
+                        Some(2) => {
+                            i += 4;
+                            Color::Rgb(
+                                nums.get(i - 2).copied().unwrap_or(0) as u8,
+                                nums.get(i - 1).copied().unwrap_or(0) as u8,
+                                nums.get(i).copied().unwrap_or(0) as u8,
+                            )
+                        }
+                        _ => Color::Default,
+                    };
+
+                    if p == 38 {
+                        st.fg = cl;
+                    }
+                    else {
+                        st.bg = cl;
+                    }
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+    }
+}
+
+/// Returns SGR sequence setting the cell style from scratch
+pub fn sgr_encode(cell: &Cell) -> String {
+    use core::fmt::Write;
+
+    let mut out = String::from("\x1b[0");
+    let attrs = [
+        (attr::BOLD, 1),
+        (attr::FAINT, 2),
+        (attr::ITALICS, 3),
+        (attr::UNDERLINE, 4),
+        (attr::BLINK, 5),
+        (attr::INVERSE, 7),
+    ];
+
+    for (bit, code) in attrs.iter() {
+        if cell.attr & bit != 0 {
+            let _ = write!(out, ";{}", code);
+        }
+    }
+
+    for (cl, base) in [(cell.fg, 30), (cell.bg, 40)] {
+        let _ = match cl {
+            Color::Default => Ok(()),
+            Color::Idx(i @ 0..=7) => write!(out, ";{}", base + i as u16),
+            Color::Idx(i @ 8..=15) => write!(out, ";{}", base + 60 + i as u16 - 8),
+            Color::Idx(i) => write!(out, ";{};5;{}", base + 8, i),
+            Color::Rgb(r, g, b) => write!(out, ";{};2;{};{};{}", base + 8, r, g, b),
+        };
+    }
+
+    out.push('m');
+    out
+}
+
+/// Number of screen columns occupied by the character
+pub fn char_width(ch: char) -> u8 {
+    match ch as u32 {
+        // combining marks, zero-width space/joiner, variation selectors
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
@@ -0,0 +1 @@
+../tui_full/clock.rs
\ No newline at end of file
@@ -0,0 +1 @@
+../tui_full/input_libc_tty.rs
\ No newline at end of file
@@ -0,0 +1 @@
+../tui_full/input_source.rs
\ No newline at end of file
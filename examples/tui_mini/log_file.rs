@@ -0,0 +1 @@
+../tui_full/log_file.rs
\ No newline at end of file
@@ -12,15 +12,23 @@ use rtwins::wgt::prop;
 use rtwins::wgt::*;
 use rtwins::TERM;
 
+use crate::input_source::{InputResult, InputSource};
+
 extern crate alloc;
 use alloc::boxed::Box;
 use alloc::format;
 use alloc::vec;
 use alloc::vec::Vec;
 
+// the LABEL_DATE of tui_full is not used here
+#[allow(dead_code)]
+mod clock;
+mod input_source;
 #[cfg(target_os = "linux")]
 mod input_libc_tty;
 #[cfg(target_os = "linux")]
+mod log_file;
+#[cfg(target_os = "linux")]
 mod pal_std;
 
 #[cfg(target_os = "none")]
@@ -28,13 +36,20 @@ use alloc_cortex_m::CortexMHeap;
 // use embedded_alloc::Heap; // TODO: linker error when used
 #[cfg(target_os = "none")]
 use cortex_m_rt::entry;
-#[cfg(target_os = "none")]
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 use cortex_m_semihosting::debug;
-#[cfg(target_os = "none")]
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 use panic_semihosting as _;
+// no semihosting host to report to
+#[cfg(all(target_os = "none", feature = "uart"))]
+use panic_halt as _;
 
-#[cfg(target_os = "none")]
+#[cfg(all(target_os = "none", not(feature = "uart")))]
 mod pal_semihosting;
+// under QEMU, also the semihosting console input
+#[cfg(all(target_os = "none", any(feature = "uart", feature = "qemu")))]
+#[cfg_attr(not(feature = "uart"), allow(dead_code))]
+mod pal_uart;
 
 // ---------------------------------------------------------------------------------------------- //
 
@@ -199,13 +214,19 @@ fn main() {
 fn main() -> ! {
     tui();
 
-    if cfg!(feature = "qemu") {
+    #[cfg(all(feature = "qemu", not(feature = "uart")))]
+    {
         // exit QEMU
         // NOTE do not run this on hardware; it can corrupt OpenOCD state
         debug::exit(debug::EXIT_SUCCESS);
     }
 
-    loop {}
+    // exit QEMU started with `-no-reboot`, as the semihosting is not enabled
+    #[cfg(all(feature = "qemu", feature = "uart"))]
+    cortex_m::peripheral::SCB::sys_reset();
+
+    #[cfg(not(all(feature = "qemu", feature = "uart")))]
+    loop {};
 }
 
 fn tui() {
@@ -220,21 +241,38 @@ fn tui() {
     let cp = cortex_m::Peripherals::take().unwrap();
 
     #[cfg(target_os = "none")]
+    clock::init(cp.SYST);
+
+    #[cfg(all(target_os = "none", not(feature = "uart")))]
+    {
+        TERM.try_lock().unwrap().pal = Box::new(pal_semihosting::SemihostingPal::new());
+    }
+
+    #[cfg(all(target_os = "none", feature = "uart"))]
     {
-        let delay = cortex_m::delay::Delay::new(cp.SYST, 32_000_000);
-        TERM.try_lock().unwrap().pal = Box::new(pal_semihosting::SemihostingPal::new(delay));
+        TERM.try_lock().unwrap().pal = Box::new(pal_uart::UartPal::new());
     }
 
+    // register function providing traces timestamp
+    rtwins::tr_set_timestr_function!(|| clock::now().time_str());
+
     #[cfg(target_os = "linux")]
     {
         // replace default PAL with our own:
-        TERM.try_lock().unwrap().pal = Box::new(pal_std::DemoPal::new());
+        let mut pal: Box<dyn rtwins::pal::Pal> = Box::new(pal_std::DemoPal::new());
 
-        // register function providing traces timestamp
-        rtwins::tr_set_timestr_function!(|| {
-            let local_time = chrono::Local::now();
-            local_time.format("%H:%M:%S%.3f ").to_string()
-        });
+        // --log=logs.txt
+        let log_path = std::env::args()
+            .find_map(|arg| arg.strip_prefix("--log=").map(|p| p.to_owned()));
+
+        if let Some(path) = log_path {
+            match log_file::LogFile::open(&path) {
+                Ok(log_file) => pal = Box::new(log_file::LogPal::new(pal, log_file)),
+                Err(e) => eprintln!("Cannot open log '{}' : {:?}", path, e.kind()),
+            }
+        }
+
+        TERM.try_lock().unwrap().pal = pal;
     }
 
     // create window state:
@@ -269,29 +307,57 @@ fn tui() {
     rtwins::tr_flush!(&mut TERM.try_lock().unwrap());
 
     #[cfg(target_os = "linux")]
-    let mut inp = input_libc_tty::InputTty::new(None, 100);
-    #[cfg(target_os = "none")]
-    let mut inp = pal_semihosting::InputSemiHost::new();
+    let mut inp: Box<dyn InputSource> = Box::new(input_libc_tty::InputTty::new(None));
+    #[cfg(all(target_os = "none", not(feature = "uart")))]
+    let mut inp: Box<dyn InputSource> = Box::new(pal_semihosting::InputSemiHost::new());
+    #[cfg(all(target_os = "none", not(feature = "uart")))]
+    if !pal_semihosting::INPUT_TIMEOUT_HONOURED {
+        rtwins::tr_warn!("Input blocks until a key is pressed");
+    }
+    #[cfg(all(target_os = "none", feature = "uart"))]
+    let mut inp: Box<dyn InputSource> = Box::new(pal_uart::InputUart::new());
 
     let mut ique = rtwins::input_decoder::InputQue::default();
     let mut dec = rtwins::input_decoder::Decoder::default();
     let mut ii = rtwins::input::InputInfo::default();
 
     #[allow(unused_labels)]
-    'mainloop: loop  {
-        let (inp_seq, q) = inp.read_input();
+    'mainloop: loop {
+        let inp_seq = match inp.read_input(100) {
+            InputResult::Data(seq) => seq,
+            InputResult::Timeout => &[],
+            InputResult::Quit => {
+                rtwins::tr_warn!("Exit requested");
+                break;
+            }
+            InputResult::Resumed => {
+                let mut term_guard = TERM.try_lock().unwrap();
+                term_guard.mouse_mode(rtwins::MouseMode::M2);
+                term_guard.screen_clr_all();
+                term_guard.draw_wnd(&mut ws_main);
+                &[]
+            }
+            // bracketed paste is not enabled
+            InputResult::Paste(_) => &[],
+            InputResult::Resized { .. } => {
+                let mut term_guard = TERM.try_lock().unwrap();
+                term_guard.screen_clr_all();
+                term_guard.draw_wnd(&mut ws_main);
+                &[]
+            }
+            InputResult::Error(e) => {
+                rtwins::tr_err!("Input error: {:?}", e);
+                break;
+            }
+        };
 
-        if q {
-            rtwins::tr_warn!("Exit requested");
-            break;
-        }
-        else if !inp_seq.is_empty() {
+        if !inp_seq.is_empty() {
             ique.extend(inp_seq.iter());
 
             while dec.decode_input_seq(&mut ique, &mut ii) > 0 {
-                // check for Ctrl+D
+                // check for Ctrl+D, also when received with other keys
                 #[cfg(target_os = "none")]
-                if let InputEvent::Char(ref cb) = ii.evnt {
+                if let rtwins::input::InputEvent::Char(ref cb) = ii.evnt {
                     if cb.as_str() == "D" && ii.kmod.has_ctrl() {
                         rtwins::tr_warn!("Exit requested");
                         break 'mainloop;
@@ -0,0 +1 @@
+../tui_full/pal_semihosting.rs
\ No newline at end of file
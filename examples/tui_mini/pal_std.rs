@@ -0,0 +1 @@
+../tui_full/pal_std.rs
\ No newline at end of file
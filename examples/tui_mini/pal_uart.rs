@@ -0,0 +1 @@
+../tui_full/pal_uart.rs
\ No newline at end of file
@@ -5,9 +5,19 @@ MEMORY
 
   /* These values correspond to the LM3S6965, one of the few devices QEMU can emulate */
   FLASH : ORIGIN = 0x00000000, LENGTH = 256K
-  RAM : ORIGIN = 0x20000000, LENGTH = 64K
+  RAM : ORIGIN = 0x20000000, LENGTH = 63K
+  /* tui_full window state, kept over the reset; see the `persist_ram` feature */
+  PERSIST : ORIGIN = 0x2000FC00, LENGTH = 1K
 }
 
+/* not initialized by the runtime */
+SECTIONS {
+  .persist (NOLOAD) : ALIGN(4) {
+    *(.persist);
+    . = ALIGN(4);
+  } > PERSIST
+} INSERT AFTER .bss;
+
 /* This is where the call stack will be allocated. */
 /* The stack is of the full descending type. */
 /* You may want to use this variable to locate the call stack and static